@@ -3,6 +3,10 @@ use std::fs;
 
 use serde::Deserialize;
 
+use crate::export::MdStyle;
+use crate::heatmap::ColorBy;
+use crate::icons::IconSet;
+use crate::term::HyperlinkMode;
 use crate::tree::SortMode;
 
 /// Main configuration structure for Kree.
@@ -22,9 +26,15 @@ pub struct KreeConfig {
     #[serde(default)]
     pub ignore: IgnoreConfig,
     /// Custom icon mappings for file extensions or filenames.
-    /// Key: extension/filename, Value: Nerd Font icon character.
+    /// Key: extension, filename, `dir:<name>` or special key; Value: icon.
     #[serde(default)]
     pub icons: HashMap<String, String>,
+    /// LS_COLORS / dircolors integration.
+    #[serde(default)]
+    pub ls_colors: LsColorsConfig,
+    /// Ordered style rules (`[[styles]]` tables). The first matching rule wins.
+    #[serde(default)]
+    pub styles: Vec<StyleRuleConfig>,
 }
 
 /// Default configuration values that can be overridden by CLI arguments.
@@ -40,8 +50,26 @@ pub struct DefaultsConfig {
     pub all: Option<bool>,
     /// Default setting for showing icons.
     pub icons: Option<bool>,
+    /// Icon set ("nerd", "emoji", "ascii" or "unicode").
+    pub icon_set: Option<String>,
+    /// Default heatmap mode ("mtime", "size" or "depth").
+    pub color_by: Option<String>,
+    /// Default setting for taking notes from leading doc comments.
+    pub doc_notes: Option<bool>,
+    /// Default setting for merging single-child directory chains.
+    pub compact: Option<bool>,
+    /// Default setting for content-based file type detection.
+    pub detect: Option<bool>,
     /// Default setting for disabling .gitignore support.
     pub no_gitignore: Option<bool>,
+    /// Default hyperlink mode ("auto", "always" or "never").
+    pub hyperlink: Option<String>,
+    /// Color theme name (built-in or a file in `~/.kree/themes/`).
+    pub theme: Option<String>,
+    /// Markdown export layout ("list", "tree" or "table").
+    pub md_style: Option<String>,
+    /// Default setting for linking entries in the Markdown export.
+    pub md_links: Option<bool>,
 }
 
 /// Configuration for ignore patterns.
@@ -52,6 +80,37 @@ pub struct IgnoreConfig {
     pub patterns: Vec<String>,
 }
 
+/// Configuration for LS_COLORS support.
+#[derive(Debug, Default, Deserialize)]
+pub struct LsColorsConfig {
+    /// Color entries using `LS_COLORS` (or `dircolors`) rules. Off by default.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Path to a `dircolors` database file to use instead of `$LS_COLORS`.
+    pub dircolors: Option<String>,
+}
+
+/// A style rule from a `[[styles]]` table.
+#[derive(Debug, Default, Deserialize)]
+pub struct StyleRuleConfig {
+    /// Glob (`*_test.rs`, `{README,LICENSE}*`) or class (`dir`, `dir:src`,
+    /// `executable`, `symlink`, `broken_symlink`).
+    #[serde(rename = "match")]
+    pub pattern: String,
+    /// Foreground color name or hex code.
+    pub fg: Option<String>,
+    /// Background color name or hex code.
+    pub bg: Option<String>,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub italic: bool,
+    #[serde(default)]
+    pub underline: bool,
+    #[serde(default)]
+    pub dim: bool,
+}
+
 impl KreeConfig {
     /// Loads the configuration from `~/.kreerc`.
     ///
@@ -87,6 +146,67 @@ impl KreeConfig {
             }
         })
     }
+
+    /// Resolves the configured hyperlink mode into a `HyperlinkMode` enum.
+    ///
+    /// Returns `None` if no mode is configured or if the configured string is invalid.
+    pub fn hyperlink_mode(&self) -> Option<HyperlinkMode> {
+        self.defaults.hyperlink.as_deref().and_then(|s| match s {
+            "auto" => Some(HyperlinkMode::Auto),
+            "always" => Some(HyperlinkMode::Always),
+            "never" => Some(HyperlinkMode::Never),
+            other => {
+                eprintln!("Warning: unknown hyperlink mode '{other}' in ~/.kreerc, ignoring");
+                None
+            }
+        })
+    }
+
+    /// Resolves the configured heatmap mode into a `ColorBy` enum.
+    ///
+    /// Returns `None` if no mode is configured or if the configured string is invalid.
+    pub fn color_by(&self) -> Option<ColorBy> {
+        self.defaults.color_by.as_deref().and_then(|s| match s {
+            "mtime" => Some(ColorBy::Mtime),
+            "size" => Some(ColorBy::Size),
+            "depth" => Some(ColorBy::Depth),
+            other => {
+                eprintln!("Warning: unknown color-by mode '{other}' in ~/.kreerc, ignoring");
+                None
+            }
+        })
+    }
+
+    /// Resolves the configured icon set into an `IconSet` enum.
+    ///
+    /// Returns `None` if no set is configured or if the configured string is invalid.
+    pub fn icon_set(&self) -> Option<IconSet> {
+        self.defaults.icon_set.as_deref().and_then(|s| match s {
+            "nerd" => Some(IconSet::Nerd),
+            "emoji" => Some(IconSet::Emoji),
+            "ascii" => Some(IconSet::Ascii),
+            "unicode" | "unicode-basic" => Some(IconSet::Unicode),
+            other => {
+                eprintln!("Warning: unknown icon set '{other}' in ~/.kreerc, ignoring");
+                None
+            }
+        })
+    }
+
+    /// Resolves the configured Markdown export layout into an `MdStyle` enum.
+    ///
+    /// Returns `None` if no layout is configured or if the configured string is invalid.
+    pub fn md_style(&self) -> Option<MdStyle> {
+        self.defaults.md_style.as_deref().and_then(|s| match s {
+            "list" => Some(MdStyle::List),
+            "tree" => Some(MdStyle::Tree),
+            "table" => Some(MdStyle::Table),
+            other => {
+                eprintln!("Warning: unknown Markdown style '{other}' in ~/.kreerc, ignoring");
+                None
+            }
+        })
+    }
 }
 
 #[cfg(test)]
@@ -128,4 +248,64 @@ mod tests {
     fn sort_mode_invalid() {
         assert!(config_with_sort(Some("bogus")).sort_mode().is_none());
     }
+
+    fn config_with_hyperlink(mode: Option<&str>) -> KreeConfig {
+        KreeConfig {
+            defaults: DefaultsConfig {
+                hyperlink: mode.map(|s| s.to_string()),
+                ..DefaultsConfig::default()
+            },
+            ..KreeConfig::default()
+        }
+    }
+
+    #[test]
+    fn hyperlink_mode_always() {
+        assert!(matches!(
+            config_with_hyperlink(Some("always")).hyperlink_mode(),
+            Some(HyperlinkMode::Always)
+        ));
+    }
+
+    #[test]
+    fn icon_set_from_config() {
+        let config: KreeConfig = toml::from_str("[defaults]\nicon_set = \"emoji\"\n").unwrap();
+        assert_eq!(config.icon_set(), Some(IconSet::Emoji));
+    }
+
+    #[test]
+    fn md_style_from_config() {
+        let config: KreeConfig = toml::from_str("[defaults]\nmd_style = \"table\"\n").unwrap();
+        assert_eq!(config.md_style(), Some(MdStyle::Table));
+    }
+
+    #[test]
+    fn style_rules_parse_in_order() {
+        let config: KreeConfig = toml::from_str(
+            r##"
+            [[styles]]
+            match = "*_test.rs"
+            fg = "green"
+            italic = true
+
+            [[styles]]
+            match = "dir:src"
+            fg = "#FF0000"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.styles.len(), 2);
+        assert_eq!(config.styles[0].pattern, "*_test.rs");
+        assert!(config.styles[0].italic);
+        assert_eq!(config.styles[1].fg.as_deref(), Some("#FF0000"));
+    }
+
+    #[test]
+    fn hyperlink_mode_invalid() {
+        assert!(
+            config_with_hyperlink(Some("sometimes"))
+                .hyperlink_mode()
+                .is_none()
+        );
+    }
 }
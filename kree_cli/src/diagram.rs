@@ -0,0 +1,352 @@
+//! Diagram exports: Graphviz DOT, Mermaid and PlantUML (`-F dot`,
+//! `-F mermaid`, `-F mermaid-mindmap`, `-F plantuml`).
+//!
+//! Labels take their color and weight from the active `Styler`, except the
+//! plain file color, which is left to the diagram's default so names stay
+//! readable on light backgrounds. Directories, files and symlinks get
+//! distinct shapes, and notes are shown as a second label line where the
+//! format allows it. The depth is limited by `-d` like the tree itself.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::style::Styler;
+use crate::tree::TreeNode;
+
+/// Label styling of one entry.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct LabelStyle {
+    /// `#rrggbb` text color, `None` for the diagram default.
+    color: Option<String>,
+    bold: bool,
+    italic: bool,
+}
+
+fn label_style(node: &TreeNode, styler: &Styler) -> LabelStyle {
+    let style = styler.style_for(&node.path, node.is_dir, node.is_symlink, node.file_kind);
+    LabelStyle {
+        color: style
+            .fg
+            .filter(|&fg| fg != styler.ui().file)
+            .map(|fg| fg.to_hex()),
+        bold: style.bold,
+        italic: style.italic || node.is_symlink,
+    }
+}
+
+/// Exports the tree as a Graphviz `digraph`, laid out left to right.
+pub fn export_dot(root: &TreeNode, styler: &Styler) -> String {
+    let mut nodes = String::new();
+    let mut edges = String::new();
+    write_dot_node(root, styler, &mut 0, &mut nodes, &mut edges);
+    format!(
+        "digraph tree {{\n    \
+         rankdir=LR;\n    \
+         node [shape=box, style=rounded, fontname=\"Helvetica\"];\n    \
+         edge [arrowhead=none];\n\
+         {nodes}{edges}}}\n"
+    )
+}
+
+/// Writes the node statement of `node` and the edges to its children;
+/// returns the node's id.
+fn write_dot_node(
+    node: &TreeNode,
+    styler: &Styler,
+    next_id: &mut usize,
+    nodes: &mut String,
+    edges: &mut String,
+) -> usize {
+    let id = *next_id;
+    *next_id += 1;
+
+    let mut label = dot_escape(&node.name);
+    if let Some(note) = &node.note {
+        label.push_str("\\n");
+        label.push_str(&dot_escape(note));
+    }
+    let mut attrs = vec![format!("label=\"{label}\"")];
+    if node.is_dir {
+        attrs.push("shape=folder".to_string());
+    } else if node.is_symlink {
+        attrs.push("style=\"rounded,dashed\"".to_string());
+    }
+    let style = label_style(node, styler);
+    if let Some(color) = &style.color {
+        attrs.push(format!("fontcolor=\"{color}\""));
+    }
+    match (style.bold, style.italic) {
+        (true, true) => attrs.push("fontname=\"Helvetica-BoldOblique\"".to_string()),
+        (true, false) => attrs.push("fontname=\"Helvetica-Bold\"".to_string()),
+        (false, true) => attrs.push("fontname=\"Helvetica-Oblique\"".to_string()),
+        (false, false) => {}
+    }
+    let _ = writeln!(nodes, "    n{id} [{}];", attrs.join(", "));
+
+    for child in &node.children {
+        let child_id = write_dot_node(child, styler, next_id, nodes, edges);
+        let _ = writeln!(edges, "    n{id} -> n{child_id};");
+    }
+    id
+}
+
+/// Escapes a DOT quoted string.
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Exports the tree as a Mermaid `graph TD` flowchart.
+///
+/// Entries with the same label style share a `classDef`.
+pub fn export_mermaid(root: &TreeNode, styler: &Styler) -> String {
+    let mut lines = String::new();
+    let mut classes: BTreeMap<LabelStyle, Vec<usize>> = BTreeMap::new();
+    write_mermaid_node(root, None, styler, &mut 0, &mut lines, &mut classes);
+
+    let mut output = format!("graph TD\n{lines}");
+    let styled = classes.iter().filter_map(|(style, ids)| {
+        let mut css = Vec::new();
+        if let Some(color) = &style.color {
+            css.push(format!("color:{color}"));
+        }
+        if style.bold {
+            css.push("font-weight:bold".to_string());
+        }
+        if style.italic {
+            css.push("font-style:italic".to_string());
+        }
+        (!css.is_empty()).then_some((css, ids))
+    });
+    for (i, (css, ids)) in styled.enumerate() {
+        let ids: Vec<String> = ids.iter().map(|id| format!("n{id}")).collect();
+        let _ = writeln!(output, "    classDef s{i} {}", css.join(","));
+        let _ = writeln!(output, "    class {} s{i}", ids.join(","));
+    }
+    output
+}
+
+fn write_mermaid_node(
+    node: &TreeNode,
+    parent: Option<usize>,
+    styler: &Styler,
+    next_id: &mut usize,
+    lines: &mut String,
+    classes: &mut BTreeMap<LabelStyle, Vec<usize>>,
+) {
+    let id = *next_id;
+    *next_id += 1;
+
+    let mut label = mermaid_escape(&node.name);
+    if let Some(note) = &node.note {
+        let _ = write!(label, "<br/><small>{}</small>", mermaid_escape(note));
+    }
+    let shape = if node.is_dir {
+        format!("[\"{label}\"]")
+    } else if node.is_symlink {
+        format!(">\"{label}\"]")
+    } else {
+        format!("(\"{label}\")")
+    };
+    match parent {
+        Some(parent) => {
+            let _ = writeln!(lines, "    n{parent} --> n{id}{shape}");
+        }
+        None => {
+            let _ = writeln!(lines, "    n{id}{shape}");
+        }
+    }
+    classes
+        .entry(label_style(node, styler))
+        .or_default()
+        .push(id);
+
+    for child in &node.children {
+        write_mermaid_node(child, Some(id), styler, next_id, lines, classes);
+    }
+}
+
+/// Exports the tree as a Mermaid `mindmap`.
+///
+/// Mindmaps do not support per-node colors, so only shapes distinguish
+/// directories (squares) from files (rounded) and symlinks (hexagons).
+pub fn export_mermaid_mindmap(root: &TreeNode) -> String {
+    let mut output = String::from("mindmap\n");
+    let mut next_id = 0;
+    write_mindmap_node(root, 1, &mut next_id, &mut output);
+    output
+}
+
+fn write_mindmap_node(node: &TreeNode, depth: usize, next_id: &mut usize, output: &mut String) {
+    let id = *next_id;
+    *next_id += 1;
+
+    let label = mermaid_escape(&node.name);
+    let shape = if node.is_dir {
+        format!("[\"{label}\"]")
+    } else if node.is_symlink {
+        format!("{{{{\"{label}\"}}}}")
+    } else {
+        format!("(\"{label}\")")
+    };
+    let _ = writeln!(output, "{}n{id}{shape}", "  ".repeat(depth));
+    for child in &node.children {
+        write_mindmap_node(child, depth + 1, next_id, output);
+    }
+}
+
+/// Replaces the characters Mermaid treats specially inside quoted labels
+/// with entity codes.
+fn mermaid_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("#quot;"),
+            '<' => escaped.push_str("#lt;"),
+            '>' => escaped.push_str("#gt;"),
+            '#' => escaped.push_str("#35;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Exports the tree as a PlantUML work breakdown structure (`@startwbs`).
+///
+/// Directories are boxes; files are drawn without a box.
+pub fn export_plantuml(root: &TreeNode, styler: &Styler) -> String {
+    let mut output = String::from("@startwbs\n");
+    write_plantuml_node(root, 1, styler, &mut output);
+    output.push_str("@endwbs\n");
+    output
+}
+
+fn write_plantuml_node(node: &TreeNode, depth: usize, styler: &Styler, output: &mut String) {
+    let style = label_style(node, styler);
+    let mut label = plantuml_escape(&node.name);
+    if style.bold {
+        label = format!("<b>{label}</b>");
+    }
+    if style.italic {
+        label = format!("<i>{label}</i>");
+    }
+    if let Some(color) = &style.color {
+        label = format!("<color:{color}>{label}</color>");
+    }
+
+    let stars = "*".repeat(depth);
+    let boxless = if node.is_dir { "" } else { "_" };
+    match &node.note {
+        Some(note) => {
+            let note = plantuml_escape(note);
+            let _ = writeln!(output, "{stars}{boxless}:{label}\n<size:10>{note}</size>;");
+        }
+        None => {
+            let _ = writeln!(output, "{stars}{boxless} {label}");
+        }
+    }
+
+    for child in &node.children {
+        write_plantuml_node(child, depth + 1, styler, output);
+    }
+}
+
+/// Escapes the characters that start Creole markup (`__init__.py` would
+/// otherwise be underlined).
+fn plantuml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '~' | '*' | '_' | '/' | '-' | '"' | '<' | '[' | ';') {
+            escaped.push('~');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::build_color_map;
+    use crate::theme::UiTheme;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn styler() -> Styler {
+        Styler::new(
+            build_color_map(&HashMap::new()),
+            None,
+            Vec::new(),
+            UiTheme::default(),
+        )
+    }
+
+    fn sample_tree() -> TreeNode {
+        let file = |name: &str| TreeNode {
+            name: name.to_string(),
+            path: PathBuf::from("missing/src").join(name),
+            ..TreeNode::default()
+        };
+        TreeNode {
+            name: "src".to_string(),
+            path: PathBuf::from("missing/src"),
+            is_dir: true,
+            children: vec![
+                TreeNode {
+                    note: Some("entry \"point\"".to_string()),
+                    ..file("main.rs")
+                },
+                file("__init__.py"),
+                file("LICENSE"),
+            ],
+            ..TreeNode::default()
+        }
+    }
+
+    #[test]
+    fn dot_nodes_and_edges() {
+        let dot = export_dot(&sample_tree(), &styler());
+        assert!(dot.starts_with("digraph tree {\n"));
+        assert!(dot.contains(
+            "    n0 [label=\"src\", shape=folder, fontcolor=\"#0000ee\", \
+             fontname=\"Helvetica-Bold\"];\n"
+        ));
+        assert!(dot.contains("n1 [label=\"main.rs\\nentry \\\"point\\\"\", fontcolor=\"#"));
+        // The plain file color is left to Graphviz
+        assert!(dot.contains("    n3 [label=\"LICENSE\"];\n"));
+        assert!(dot.contains("    n0 -> n3;\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn mermaid_flowchart() {
+        let mermaid = export_mermaid(&sample_tree(), &styler());
+        assert!(mermaid.starts_with("graph TD\n    n0[\"src\"]\n"));
+        assert!(
+            mermaid.contains(
+                "    n0 --> n1(\"main.rs<br/><small>entry #quot;point#quot;</small>\")\n"
+            )
+        );
+        assert!(mermaid.contains("color:#0000ee,font-weight:bold\n"));
+        // LICENSE has no style of its own, so it gets no class
+        assert!(!mermaid.contains("n3\n"));
+    }
+
+    #[test]
+    fn mermaid_mindmap_indents_by_depth() {
+        let mindmap = export_mermaid_mindmap(&sample_tree());
+        assert_eq!(
+            mindmap,
+            "mindmap\n  n0[\"src\"]\n    n1(\"main.rs\")\n    n2(\"__init__.py\")\n    \
+             n3(\"LICENSE\")\n"
+        );
+    }
+
+    #[test]
+    fn plantuml_escapes_creole() {
+        let wbs = export_plantuml(&sample_tree(), &styler());
+        assert!(wbs.starts_with("@startwbs\n* <color:#0000ee><b>src</b></color>\n"));
+        assert!(wbs.contains("~_~_init~_~_.py"));
+        assert!(wbs.contains("<size:10>entry ~\"point~\"</size>;\n"));
+        assert!(wbs.ends_with("**_ LICENSE\n@endwbs\n"));
+    }
+}
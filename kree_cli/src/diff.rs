@@ -0,0 +1,509 @@
+//! Comparison of two trees (`--snapshot-check`, `--verify` and `--diff`).
+//!
+//! Both sides are compared in their export representation, so a tree loaded
+//! from disk can be checked against one stored with `export_json`.
+//! Entries are matched by name; the root names are not compared.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::export::{ExportNode, NodeType};
+use crate::render::format_size;
+
+/// What makes two files with the same name differ.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compare {
+    /// Only names and types.
+    Structure,
+    /// Size and modification time.
+    Metadata,
+    /// Size and file contents, ignoring modification times.
+    Content,
+    /// Size and the content digests stored by `--hash`.
+    Hash,
+}
+
+/// How an entry differs between the old and the new tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Unchanged,
+    /// Only in the new tree.
+    Added,
+    /// Only in the old tree.
+    Removed,
+    /// In both trees with a different type.
+    TypeChanged,
+    /// A file or symlink in both trees whose size, date, contents or target
+    /// differ.
+    Modified,
+}
+
+impl Change {
+    /// Marker printed before the entry name.
+    fn marker(self) -> &'static str {
+        match self {
+            Change::Unchanged => "",
+            Change::Added => "+ ",
+            Change::Removed => "- ",
+            Change::TypeChanged | Change::Modified => "~ ",
+        }
+    }
+}
+
+/// A node of the merged tree.
+#[derive(Debug, Serialize)]
+pub struct DiffNode {
+    pub name: String,
+    /// Type in the new tree, or in the old one for removed entries.
+    #[serde(rename = "type")]
+    pub node_type: NodeType,
+    #[serde(rename = "status")]
+    pub change: Change,
+    /// Type in the old tree, for type changes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_type: Option<NodeType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_size: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<DiffNode>,
+}
+
+impl DiffNode {
+    /// Returns true if this node or any descendant changed.
+    pub fn has_changes(&self) -> bool {
+        self.change != Change::Unchanged || self.children.iter().any(DiffNode::has_changes)
+    }
+}
+
+/// Number of changed entries, by kind.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DiffCounts {
+    pub added: usize,
+    pub removed: usize,
+    pub type_changed: usize,
+    pub modified: usize,
+}
+
+/// Compares `new` against `old`.
+pub fn diff_trees(old: &ExportNode, new: &ExportNode, compare: Compare) -> DiffNode {
+    DiffNode {
+        children: diff_children(&old.children, &new.children, compare),
+        ..node(new, Change::Unchanged)
+    }
+}
+
+fn diff_children(old: &[ExportNode], new: &[ExportNode], compare: Compare) -> Vec<DiffNode> {
+    let mut nodes: Vec<DiffNode> = Vec::new();
+
+    for new_child in new {
+        let diff = match old.iter().find(|o| o.name == new_child.name) {
+            None => whole(new_child, Change::Added),
+            Some(old_child) if old_child.node_type != new_child.node_type => DiffNode {
+                old_type: Some(old_child.node_type),
+                ..node(new_child, Change::TypeChanged)
+            },
+            Some(old_child) if new_child.node_type == NodeType::Directory => DiffNode {
+                children: diff_children(&old_child.children, &new_child.children, compare),
+                ..node(new_child, Change::Unchanged)
+            },
+            Some(old_child) => {
+                let change = if entries_differ(old_child, new_child, compare) {
+                    Change::Modified
+                } else {
+                    Change::Unchanged
+                };
+                DiffNode {
+                    old_size: old_child.size,
+                    ..node(new_child, change)
+                }
+            }
+        };
+        nodes.push(diff);
+    }
+    for old_child in old {
+        if !new.iter().any(|n| n.name == old_child.name) {
+            nodes.push(whole(old_child, Change::Removed));
+        }
+    }
+
+    // Directories first, then by name, like `--sort kind`
+    nodes.sort_by(|a, b| {
+        (b.node_type == NodeType::Directory)
+            .cmp(&(a.node_type == NodeType::Directory))
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    nodes
+}
+
+/// Creates a leaf diff node for `entry`, sized from the side it comes from.
+fn node(entry: &ExportNode, change: Change) -> DiffNode {
+    // Directory sizes are filesystem details, not content
+    let size = entry
+        .size
+        .filter(|_| entry.node_type != NodeType::Directory);
+    let (old_size, new_size) = match change {
+        Change::Removed => (size, None),
+        _ => (None, size),
+    };
+    DiffNode {
+        name: entry.name.clone(),
+        node_type: entry.node_type,
+        change,
+        old_type: None,
+        old_size,
+        new_size,
+        children: Vec::new(),
+    }
+}
+
+/// Marks `entry` and its whole subtree with `change`.
+fn whole(entry: &ExportNode, change: Change) -> DiffNode {
+    DiffNode {
+        children: entry.children.iter().map(|c| whole(c, change)).collect(),
+        ..node(entry, change)
+    }
+}
+
+/// Compares two non-directory entries of the same type. Values missing on
+/// either side (e.g. a snapshot saved without metadata) never differ.
+fn entries_differ(old: &ExportNode, new: &ExportNode, compare: Compare) -> bool {
+    fn known_and_differ<T: PartialEq>(a: &Option<T>, b: &Option<T>) -> bool {
+        matches!((a, b), (Some(a), Some(b)) if a != b)
+    }
+
+    if compare == Compare::Structure {
+        return false;
+    }
+    if known_and_differ(&old.symlink_target, &new.symlink_target)
+        || known_and_differ(&old.size, &new.size)
+    {
+        return true;
+    }
+    match compare {
+        Compare::Metadata => known_and_differ(&old.modified, &new.modified),
+        Compare::Content => !same_contents(Path::new(&old.path), Path::new(&new.path)),
+        // A file that can no longer be read does not match its digest
+        Compare::Hash => old.hash.is_some() && old.hash != new.hash,
+        Compare::Structure => false,
+    }
+}
+
+/// Returns true if both files can be read and have identical contents.
+fn same_contents(a: &Path, b: &Path) -> bool {
+    let (Ok(a), Ok(b)) = (File::open(a), File::open(b)) else {
+        return false;
+    };
+    let (mut a, mut b) = (BufReader::new(a), BufReader::new(b));
+    let (mut buf_a, mut buf_b) = (vec![0u8; 64 * 1024], vec![0u8; 64 * 1024]);
+    loop {
+        let Ok(n) = a.read(&mut buf_a) else {
+            return false;
+        };
+        if b.read_exact(&mut buf_b[..n]).is_err() || buf_a[..n] != buf_b[..n] {
+            return false;
+        }
+        if n == 0 {
+            // `a` is exhausted; `b` must be too
+            return matches!(b.read(&mut buf_b[..1]), Ok(0));
+        }
+    }
+}
+
+/// Drops unchanged entries that have no changed descendants.
+pub fn retain_changed(node: &mut DiffNode) {
+    node.children.retain(DiffNode::has_changes);
+    for child in &mut node.children {
+        retain_changed(child);
+    }
+}
+
+/// Counts the changed entries, without descending into added or removed
+/// directories.
+pub fn count_changes(node: &DiffNode) -> DiffCounts {
+    let mut counts = DiffCounts::default();
+    count_into(node, &mut counts);
+    counts
+}
+
+fn count_into(node: &DiffNode, counts: &mut DiffCounts) {
+    match node.change {
+        Change::Added => counts.added += 1,
+        Change::Removed => counts.removed += 1,
+        Change::TypeChanged => counts.type_changed += 1,
+        Change::Modified => counts.modified += 1,
+        Change::Unchanged => node.children.iter().for_each(|c| count_into(c, counts)),
+    }
+}
+
+/// Formats a summary line such as `1 added, 2 removed`, leaving out kinds
+/// without changes.
+pub fn format_counts(counts: &DiffCounts) -> String {
+    join_counts(&[
+        (counts.added, "added"),
+        (counts.removed, "removed"),
+        (counts.modified, "modified"),
+        (counts.type_changed, "type changed"),
+    ])
+}
+
+/// Formats the summary of `--verify`, where added entries are extra
+/// files and removed entries are missing ones.
+pub fn format_verify_counts(counts: &DiffCounts) -> String {
+    join_counts(&[
+        (counts.modified, "modified"),
+        (counts.removed, "missing"),
+        (counts.added, "extra"),
+        (counts.type_changed, "type changed"),
+    ])
+}
+
+fn join_counts(counts: &[(usize, &str)]) -> String {
+    let parts: Vec<String> = counts
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, label)| format!("{n} {label}"))
+        .collect();
+    if parts.is_empty() {
+        "no changes".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Formats the merged tree with `+`, `-` and `~` markers.
+///
+/// Directories get a trailing `/` so types stay visible without colors.
+/// With `collapse`, added and removed directories are shown without their
+/// contents.
+pub fn format_diff(root: &DiffNode, collapse: bool, color: bool) -> String {
+    let mut output = format!("└── {}\n", root.name);
+    format_children(&root.children, "     ", collapse, color, &mut output);
+    output
+}
+
+fn format_children(
+    nodes: &[DiffNode],
+    prefix: &str,
+    collapse: bool,
+    color: bool,
+    output: &mut String,
+) {
+    for (i, node) in nodes.iter().enumerate() {
+        let is_last = i == nodes.len() - 1;
+        let connector = if is_last { "└── " } else { "├── " };
+        let slash = if node.node_type == NodeType::Directory {
+            "/"
+        } else {
+            ""
+        };
+        let label = format!("{}{}{slash}", node.change.marker(), node.name);
+        let label = if color {
+            match node.change {
+                Change::Unchanged => label,
+                Change::Added => label.green().to_string(),
+                Change::Removed => label.red().to_string(),
+                Change::TypeChanged | Change::Modified => label.yellow().to_string(),
+            }
+        } else {
+            label
+        };
+        let detail = change_detail(node);
+        let detail = if color && !detail.is_empty() {
+            detail.dimmed().to_string()
+        } else {
+            detail
+        };
+        output.push_str(&format!("{prefix}{connector}{label}{detail}\n"));
+
+        let collapsed = collapse && matches!(node.change, Change::Added | Change::Removed);
+        if !collapsed {
+            let child_prefix = format!("{prefix}{}", if is_last { "     " } else { "│    " });
+            format_children(&node.children, &child_prefix, collapse, color, output);
+        }
+    }
+}
+
+/// Returns a suffix explaining a type or size change.
+fn change_detail(node: &DiffNode) -> String {
+    match (node.change, node.old_type, node.old_size, node.new_size) {
+        (Change::TypeChanged, Some(old), _, _) => format!(" (was {})", old.as_str()),
+        (Change::Modified, _, Some(old), Some(new)) if old != new => {
+            format!(" ({} → {})", format_size(old), format_size(new))
+        }
+        _ => String::new(),
+    }
+}
+
+/// Exports the merged tree as JSON.
+pub fn diff_json(root: &DiffNode) -> String {
+    serde_json::to_string_pretty(root).unwrap_or_else(|e| format!("Error: {e}"))
+}
+
+/// Exports the merged tree as YAML.
+pub fn diff_yaml(root: &DiffNode) -> String {
+    serde_yml::to_string(root).unwrap_or_else(|e| format!("Error: {e}"))
+}
+
+/// Exports the merged tree as a Markdown list, with changed entries marked
+/// like `+ **build**/`.
+pub fn diff_markdown(root: &DiffNode) -> String {
+    let mut output = format!("- **{}**\n", root.name);
+    for child in &root.children {
+        write_markdown(child, 1, &mut output);
+    }
+    output
+}
+
+fn write_markdown(node: &DiffNode, depth: usize, output: &mut String) {
+    let indent = "  ".repeat(depth);
+    let marker = node.change.marker();
+    let detail = change_detail(node);
+    if node.node_type == NodeType::Directory {
+        output.push_str(&format!("{indent}- {marker}**{}**/{detail}\n", node.name));
+    } else {
+        output.push_str(&format!("{indent}- {marker}{}{detail}\n", node.name));
+    }
+    for child in &node.children {
+        write_markdown(child, depth + 1, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::import_tree;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn tree(json: &str) -> ExportNode {
+        import_tree(json).unwrap()
+    }
+
+    fn sample() -> (ExportNode, ExportNode) {
+        let old = tree(
+            r#"{"name": ".", "path": ".", "type": "directory", "children": [
+                {"name": "docs", "path": "./docs", "type": "directory", "children": [
+                    {"name": "guide.md", "path": "./docs/guide.md", "type": "file"}
+                ]},
+                {"name": "src", "path": "./src", "type": "directory", "children": [
+                    {"name": "main.rs", "path": "./src/main.rs", "type": "file", "size": 10},
+                    {"name": "util", "path": "./src/util", "type": "file"}
+                ]}
+            ]}"#,
+        );
+        let new = tree(
+            r#"{"name": ".", "path": ".", "type": "directory", "children": [
+                {"name": "build", "path": "./build", "type": "directory", "children": [
+                    {"name": "out.o", "path": "./build/out.o", "type": "file"}
+                ]},
+                {"name": "src", "path": "./src", "type": "directory", "children": [
+                    {"name": "main.rs", "path": "./src/main.rs", "type": "file", "size": 2048},
+                    {"name": "util", "path": "./src/util", "type": "directory"}
+                ]}
+            ]}"#,
+        );
+        (old, new)
+    }
+
+    #[test]
+    fn identical_trees_have_no_changes() {
+        let (old, _) = sample();
+        let diff = diff_trees(&old, &old, Compare::Metadata);
+        assert!(!diff.has_changes());
+        assert_eq!(count_changes(&diff), DiffCounts::default());
+    }
+
+    #[test]
+    fn detects_added_removed_and_type_changes() {
+        let (old, new) = sample();
+        let diff = diff_trees(&old, &new, Compare::Structure);
+        assert_eq!(
+            count_changes(&diff),
+            DiffCounts {
+                added: 1,
+                removed: 1,
+                type_changed: 1,
+                modified: 0,
+            }
+        );
+        assert_eq!(diff.children[0].name, "build");
+        assert_eq!(diff.children[0].children[0].change, Change::Added);
+
+        let diff = diff_trees(&old, &new, Compare::Metadata);
+        assert_eq!(count_changes(&diff).modified, 1);
+    }
+
+    #[test]
+    fn formats_merged_tree() {
+        let (old, new) = sample();
+        let mut diff = diff_trees(&old, &new, Compare::Metadata);
+        assert_eq!(
+            format_diff(&diff, true, false),
+            "└── .\n     ├── + build/\n     ├── - docs/\n     └── src/\n          ├── ~ util/ (was file)\n          └── ~ main.rs (10B → 2.0K)\n"
+        );
+
+        diff.children[2].children[1].change = Change::Unchanged;
+        retain_changed(&mut diff);
+        assert_eq!(diff.children[2].children.len(), 1);
+        assert!(diff_markdown(&diff).contains("  - + **build**/\n    - + out.o\n"));
+    }
+
+    #[test]
+    fn content_comparison_ignores_mtime() {
+        let dir = tempdir().unwrap();
+        let (a, b, c) = (
+            dir.path().join("a"),
+            dir.path().join("b"),
+            dir.path().join("c"),
+        );
+        fs::write(&a, "same").unwrap();
+        fs::write(&b, "same").unwrap();
+        fs::write(&c, "diff").unwrap();
+        assert!(same_contents(&a, &b));
+        assert!(!same_contents(&a, &c));
+
+        let entry = |path: &Path, modified: &str| ExportNode {
+            modified: Some(modified.to_string()),
+            ..tree(&format!(
+                r#"{{"name": "f", "path": "{}", "type": "file", "size": 4}}"#,
+                path.display()
+            ))
+        };
+        let (old, new) = (entry(&a, "2024-01-01"), entry(&b, "2025-01-01"));
+        assert!(entries_differ(&old, &new, Compare::Metadata));
+        assert!(!entries_differ(&old, &new, Compare::Content));
+        assert!(entries_differ(
+            &old,
+            &entry(&c, "2024-01-01"),
+            Compare::Content
+        ));
+    }
+
+    #[test]
+    fn hash_comparison_reports_changed_digests() {
+        let entry = |hash: Option<&str>| ExportNode {
+            hash: hash.map(str::to_string),
+            ..tree(r#"{"name": "f", "path": "f", "type": "file"}"#)
+        };
+        let differ = |old, new| entries_differ(&entry(old), &entry(new), Compare::Hash);
+        assert!(!differ(Some("ab"), Some("ab")));
+        assert!(differ(Some("ab"), Some("cd")));
+        // Unreadable now, or not hashed when the manifest was written
+        assert!(differ(Some("ab"), None));
+        assert!(!differ(None, Some("ab")));
+
+        let (old, new) = sample();
+        let counts = count_changes(&diff_trees(&old, &new, Compare::Structure));
+        assert_eq!(
+            format_verify_counts(&counts),
+            "1 missing, 1 extra, 1 type changed"
+        );
+    }
+}
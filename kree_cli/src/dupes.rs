@@ -0,0 +1,315 @@
+//! Duplicate file detection (`--dupes`).
+//!
+//! Candidates are narrowed in three passes, each run in parallel: files are
+//! grouped by size, then by a digest of their first bytes, and only files
+//! that still collide are hashed in full. Hard links to the same inode are
+//! one file, not duplicates. Empty files are never reported.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::hash::{self, HashAlgorithm};
+use crate::render::format_size;
+use crate::tree::TreeNode;
+
+/// Number of leading bytes hashed by the second pass.
+const HEAD_LEN: u64 = 4096;
+
+/// Algorithm of the content digests; duplicates only need a fast one.
+const ALGORITHM: HashAlgorithm = HashAlgorithm::Blake3;
+
+/// A set of files with identical contents.
+#[derive(Debug, Serialize)]
+pub struct DupeGroup {
+    /// Position of the set, from 1, largest reclaimable size first.
+    pub id: usize,
+    /// Size of each copy in bytes.
+    pub size: u64,
+    /// BLAKE3 digest of the contents.
+    pub hash: String,
+    /// One path per copy, sorted.
+    pub paths: Vec<PathBuf>,
+}
+
+impl DupeGroup {
+    /// Bytes freed by keeping a single copy.
+    pub fn reclaimable(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+/// A file that may have duplicates.
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+}
+
+/// Finds the files of the tree with identical contents.
+pub fn find_dupes(root: &TreeNode) -> Vec<DupeGroup> {
+    let mut paths = Vec::new();
+    collect_files(root, &mut paths);
+    paths.sort();
+
+    // Pass 1: stat every file; hard links share a (device, inode) id
+    let files: Vec<(Candidate, Option<(u64, u64)>)> = paths
+        .into_par_iter()
+        .filter_map(|path| {
+            let meta = fs::metadata(&path).ok()?;
+            let size = meta.len();
+            Some((Candidate { path, size }, file_id(&meta)))
+        })
+        .collect();
+    let mut seen = HashSet::new();
+    let mut by_size: HashMap<u64, Vec<Candidate>> = HashMap::new();
+    for (file, id) in files {
+        if file.size > 0 && id.is_none_or(|id| seen.insert(id)) {
+            by_size.entry(file.size).or_default().push(file);
+        }
+    }
+
+    // Pass 2: digest of the first bytes; small files are hashed in full here
+    let heads = digest_groups(by_size.into_values(), |file| {
+        hash::hash_head(&file.path, HEAD_LEN, ALGORITHM)
+    });
+
+    // Pass 3: full digest of the files larger than the head
+    let (complete, partial): (Vec<_>, Vec<_>) = heads
+        .into_iter()
+        .partition(|(_, files)| files[0].size <= HEAD_LEN);
+    let full = digest_groups(partial.into_iter().map(|(_, files)| files), |file| {
+        hash::hash_file(&file.path, ALGORITHM)
+    });
+
+    let mut groups: Vec<DupeGroup> = complete
+        .into_iter()
+        .chain(full)
+        .map(|(hash, files)| DupeGroup {
+            id: 0,
+            size: files[0].size,
+            hash,
+            paths: files.into_iter().map(|file| file.path).collect(),
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.reclaimable()
+            .cmp(&a.reclaimable())
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+    for (i, group) in groups.iter_mut().enumerate() {
+        group.id = i + 1;
+    }
+    groups
+}
+
+/// Splits each group of two or more candidates by `digest`, computed in
+/// parallel, and returns the subgroups that still have two or more files.
+fn digest_groups(
+    groups: impl Iterator<Item = Vec<Candidate>>,
+    digest: impl Fn(&Candidate) -> Option<String> + Sync,
+) -> Vec<(String, Vec<Candidate>)> {
+    let candidates: Vec<Vec<Candidate>> = groups.filter(|files| files.len() > 1).collect();
+    let digests: Vec<Vec<Option<String>>> = candidates
+        .par_iter()
+        .map(|files| files.par_iter().map(&digest).collect())
+        .collect();
+
+    let mut result = Vec::new();
+    for (files, digests) in candidates.into_iter().zip(digests) {
+        let mut by_digest: HashMap<String, Vec<Candidate>> = HashMap::new();
+        for (file, digest) in files.into_iter().zip(digests) {
+            // Unreadable files cannot be compared
+            if let Some(digest) = digest {
+                by_digest.entry(digest).or_default().push(file);
+            }
+        }
+        result.extend(by_digest.into_iter().filter(|(_, files)| files.len() > 1));
+    }
+    result
+}
+
+fn collect_files(node: &TreeNode, paths: &mut Vec<PathBuf>) {
+    for child in &node.children {
+        if child.is_dir {
+            collect_files(child, paths);
+        } else if !child.is_symlink {
+            paths.push(child.path.clone());
+        }
+    }
+}
+
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Returns the `[dup #N]` tag of every duplicate, keyed by path.
+pub fn dupe_tags(groups: &[DupeGroup]) -> HashMap<PathBuf, String> {
+    groups
+        .iter()
+        .flat_map(|group| {
+            let tag = format!("[dup #{}]", group.id);
+            group
+                .paths
+                .iter()
+                .map(move |path| (path.clone(), tag.clone()))
+        })
+        .collect()
+}
+
+/// Removes the entries that are neither in `keep` nor contain one.
+pub fn prune_tree(node: &mut TreeNode, keep: &HashMap<PathBuf, String>) {
+    node.children.retain_mut(|child| {
+        prune_tree(child, keep);
+        keep.contains_key(&child.path) || !child.children.is_empty()
+    });
+}
+
+/// Formats a summary line such as `2 duplicate sets, 3 redundant copies,
+/// 1.5M reclaimable`.
+pub fn format_summary(groups: &[DupeGroup]) -> String {
+    if groups.is_empty() {
+        return "No duplicate files".to_string();
+    }
+    let report = DupeReport::new(groups);
+    format!(
+        "{} duplicate sets, {} redundant copies, {} reclaimable",
+        groups.len(),
+        report.redundant_copies,
+        format_size(report.reclaimable_bytes)
+    )
+}
+
+/// Report written by `--dupes -F json|yaml`.
+#[derive(Serialize)]
+struct DupeReport<'a> {
+    groups: &'a [DupeGroup],
+    redundant_copies: usize,
+    reclaimable_bytes: u64,
+}
+
+impl<'a> DupeReport<'a> {
+    fn new(groups: &'a [DupeGroup]) -> Self {
+        DupeReport {
+            groups,
+            redundant_copies: groups.iter().map(|group| group.paths.len() - 1).sum(),
+            reclaimable_bytes: groups.iter().map(DupeGroup::reclaimable).sum(),
+        }
+    }
+}
+
+/// Exports the duplicate sets as JSON.
+pub fn dupes_json(groups: &[DupeGroup]) -> String {
+    serde_json::to_string_pretty(&DupeReport::new(groups)).unwrap_or_else(|e| format!("Error: {e}"))
+}
+
+/// Exports the duplicate sets as YAML.
+pub fn dupes_yaml(groups: &[DupeGroup]) -> String {
+    serde_yml::to_string(&DupeReport::new(groups)).unwrap_or_else(|e| format!("Error: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ignore::IgnoreFilter;
+    use crate::tree::{SortMode, TreeOptions, load_tree};
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn scan(root: &Path) -> TreeNode {
+        let filter = IgnoreFilter::new(false, &[]);
+        load_tree(
+            &root.to_path_buf(),
+            10,
+            0,
+            &filter,
+            SortMode::Kind,
+            &TreeOptions::default(),
+        )
+    }
+
+    #[test]
+    fn groups_identical_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("copies")).unwrap();
+        let big = "x".repeat(HEAD_LEN as usize + 10);
+        fs::write(root.join("a.bin"), &big).unwrap();
+        fs::write(root.join("copies/a.bin"), &big).unwrap();
+        // Same size and head, different tail
+        fs::write(root.join("b.bin"), format!("{}y", &big[1..])).unwrap();
+        fs::write(root.join("small.txt"), "hi").unwrap();
+        fs::write(root.join("copies/small.txt"), "hi").unwrap();
+        fs::write(root.join("copies/other.txt"), "ho").unwrap();
+        fs::write(root.join("empty1"), "").unwrap();
+        fs::write(root.join("empty2"), "").unwrap();
+
+        let groups = find_dupes(&scan(root));
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].id, 1);
+        assert_eq!(
+            groups[0].paths,
+            vec![root.join("a.bin"), root.join("copies/a.bin")]
+        );
+        assert_eq!(
+            groups[0].hash,
+            blake3::hash(big.as_bytes()).to_hex().to_string()
+        );
+        assert_eq!(groups[1].paths.len(), 2);
+        assert_eq!(groups[1].reclaimable(), 2);
+        assert_eq!(
+            format_summary(&groups),
+            "2 duplicate sets, 2 redundant copies, 4.0K reclaimable"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn hard_links_are_not_duplicates() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.txt"), "same").unwrap();
+        fs::hard_link(root.join("a.txt"), root.join("link.txt")).unwrap();
+        assert!(find_dupes(&scan(root)).is_empty());
+
+        fs::write(root.join("copy.txt"), "same").unwrap();
+        let groups = find_dupes(&scan(root));
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].paths.len(), 2);
+        assert_eq!(groups[0].reclaimable(), 4);
+    }
+
+    #[test]
+    fn prune_keeps_duplicates_and_their_parents() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::create_dir(root.join("unrelated")).unwrap();
+        fs::write(root.join("a/b/one.txt"), "dup").unwrap();
+        fs::write(root.join("two.txt"), "dup").unwrap();
+        fs::write(root.join("unrelated/three.txt"), "solo").unwrap();
+
+        let mut tree = scan(root);
+        let groups = find_dupes(&tree);
+        let tags = dupe_tags(&groups);
+        assert!(tags.contains_key(&root.join("two.txt")));
+        assert_eq!(tags[&root.join("a/b/one.txt")], "[dup #1]");
+        prune_tree(&mut tree, &tags);
+        let names: Vec<&str> = tree.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["a", "two.txt"]);
+        assert_eq!(tree.children[0].children[0].children[0].name, "one.txt");
+
+        let json: serde_json::Value = serde_json::from_str(&dupes_json(&groups)).unwrap();
+        assert_eq!(json["reclaimable_bytes"], 3);
+        assert_eq!(json["groups"][0]["paths"].as_array().unwrap().len(), 2);
+    }
+}
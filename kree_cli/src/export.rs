@@ -1,41 +1,134 @@
+use std::path::Path;
 use std::time::SystemTime;
 
-use serde::Serialize;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 
-use crate::tree::TreeNode;
+use crate::filetype;
+use crate::loc::{Languages, LineCounts};
+use crate::render::{
+    IconMap, RenderOptions, format_size, format_time, format_tree, metadata_columns,
+};
+use crate::style::Styler;
+use crate::tree::{NodeMetadata, TreeNode};
+
+/// Type of an exported entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeType {
+    Directory,
+    File,
+    Symlink,
+}
+
+impl NodeType {
+    /// Returns the name used in exports.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NodeType::Directory => "directory",
+            NodeType::File => "file",
+            NodeType::Symlink => "symlink",
+        }
+    }
+}
+
+/// Version of the export schema (`schema/export.schema.json`).
+///
+/// Bumped when a field is removed, renamed or changes meaning; new fields
+/// may be added without a bump.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema of the JSON and YAML exports, printed by `--print-schema`.
+pub const EXPORT_SCHEMA: &str = include_str!("../schema/export.schema.json");
+
+/// Envelope of the JSON and YAML exports: the tree plus how it was scanned.
+#[derive(Serialize, Deserialize)]
+pub struct ExportDocument {
+    pub schema_version: u32,
+    pub kree_version: String,
+    /// When the export was written, as an ISO 8601 UTC timestamp.
+    pub generated_at: String,
+    /// Absolute path of the scanned directory; `null` when the tree did
+    /// not come from disk (`--fromfile`, `--load`).
+    pub root_path: Option<String>,
+    pub options: ScanOptions,
+    pub tree: ExportNode,
+}
+
+/// Scan settings recorded in the export envelope.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanOptions {
+    pub depth: u32,
+    pub sort: String,
+    pub all: bool,
+    pub gitignore: bool,
+    pub dirs_only: bool,
+    pub extensions: Vec<String>,
+    pub long: bool,
+    pub detect: bool,
+    pub compact: bool,
+    /// Digest algorithm of the `hash` fields, `null` without `--hash`.
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// Lines were counted (`--loc`).
+    #[serde(default)]
+    pub loc: bool,
+}
 
 /// Serializable representation of a tree node.
-#[derive(Serialize)]
-struct ExportNode {
-    name: String,
-    path: String,
+///
+/// Every field is written on every platform: missing values are `null`
+/// and `merged`/`children` are empty arrays, so consumers can rely on the
+/// shape described by `EXPORT_SCHEMA`.
+#[derive(Serialize, Deserialize)]
+pub struct ExportNode {
+    pub name: String,
+    pub path: String,
     #[serde(rename = "type")]
-    node_type: &'static str,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    size: Option<u64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    modified: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[cfg(unix)]
-    permissions: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[cfg(unix)]
-    owner: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    symlink_target: Option<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    children: Vec<ExportNode>,
+    pub node_type: NodeType,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub modified: Option<String>,
+    /// `rwxr-xr-x` permission bits; always `null` outside Unix.
+    #[serde(default)]
+    pub permissions: Option<String>,
+    /// Owning user; always `null` outside Unix.
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub symlink_target: Option<String>,
+    #[serde(default)]
+    pub file_kind: Option<String>,
+    /// Hex digest of a file's contents (`--hash`); `null` for directories.
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// Code, comment and blank lines (`--loc`); a directory holds the
+    /// totals of the files below it.
+    #[serde(default)]
+    pub loc: Option<LineCounts>,
+    /// Directory names merged into this node by `--compact`.
+    #[serde(default)]
+    pub merged: Vec<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub children: Vec<ExportNode>,
 }
 
-fn to_export_node(node: &TreeNode) -> ExportNode {
-    let node_type = if node.path.is_dir() {
-        "directory"
+/// Returns the exported type of a tree entry.
+fn node_type(node: &TreeNode) -> NodeType {
+    if node.is_dir {
+        NodeType::Directory
     } else if node.is_symlink {
-        "symlink"
+        NodeType::Symlink
     } else {
-        "file"
-    };
+        NodeType::File
+    }
+}
 
+/// Converts a loaded tree into its export representation.
+pub fn to_export_node(node: &TreeNode) -> ExportNode {
     let (size, modified) = if let Some(ref meta) = node.metadata {
         (meta.size, meta.modified.as_ref().map(format_iso_time))
     } else {
@@ -48,23 +141,63 @@ fn to_export_node(node: &TreeNode) -> ExportNode {
     } else {
         (None, None)
     };
+    #[cfg(not(unix))]
+    let (permissions, owner) = (None, None);
 
     ExportNode {
         name: node.name.clone(),
         path: node.path.display().to_string(),
-        node_type,
+        node_type: node_type(node),
         size,
         modified,
-        #[cfg(unix)]
         permissions,
-        #[cfg(unix)]
         owner,
         symlink_target: node.symlink_target.as_ref().map(|p| p.display().to_string()),
+        file_kind: node.file_kind.map(|k| k.name.to_string()),
+        hash: node.hash.clone(),
+        loc: node.loc,
+        merged: node.merged.clone(),
+        note: node.note.clone(),
         children: node.children.iter().map(to_export_node).collect(),
     }
 }
 
-fn format_iso_time(time: &SystemTime) -> String {
+/// Converts an imported export back into a tree, restoring the stored
+/// metadata, file kinds, merged names and notes.
+pub fn from_export_node(node: ExportNode) -> TreeNode {
+    let has_unix_metadata = cfg!(unix) && (node.permissions.is_some() || node.owner.is_some());
+
+    let metadata = if node.size.is_some() || node.modified.is_some() || has_unix_metadata {
+        Some(NodeMetadata {
+            size: node.size,
+            modified: node.modified.as_deref().and_then(parse_iso_time),
+            #[cfg(unix)]
+            mode: node.permissions.as_deref().and_then(parse_mode),
+            #[cfg(unix)]
+            owner: node.owner,
+        })
+    } else {
+        None
+    };
+
+    TreeNode {
+        name: node.name,
+        path: node.path.into(),
+        is_dir: node.node_type == NodeType::Directory,
+        is_symlink: node.node_type == NodeType::Symlink || node.symlink_target.is_some(),
+        symlink_target: node.symlink_target.map(Into::into),
+        metadata,
+        file_kind: node.file_kind.as_deref().and_then(filetype::kind_named),
+        merged: node.merged,
+        note: node.note,
+        hash: node.hash,
+        loc: node.loc,
+        loc_languages: Languages::new(),
+        children: node.children.into_iter().map(from_export_node).collect(),
+    }
+}
+
+pub(crate) fn format_iso_time(time: &SystemTime) -> String {
     let duration = time
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap_or_default();
@@ -92,6 +225,40 @@ fn days_to_ymd(mut days: i64) -> (i64, i64, i64) {
     (year, m as i64 - 1, d as i64)
 }
 
+/// Parses a `YYYY-MM-DDTHH:MM:SSZ` timestamp written by `format_iso_time`.
+fn parse_iso_time(s: &str) -> Option<SystemTime> {
+    let field = |range: std::ops::Range<usize>| s.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hours, minutes, seconds) = (field(11..13)?, field(14..16)?, field(17..19)?);
+    let secs = ymd_to_days(year, month, day) * 86400 + hours * 3600 + minutes * 60 + seconds;
+    let secs = u64::try_from(secs).ok()?;
+    Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+}
+
+/// Inverse of `days_to_ymd`, with a 1-based month.
+fn ymd_to_days(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Parses an `rwxr-xr-x` string written by `format_mode`.
+fn parse_mode(s: &str) -> Option<u32> {
+    if s.len() != 9 {
+        return None;
+    }
+    let mode = s
+        .chars()
+        .enumerate()
+        .filter(|&(_, ch)| ch != '-')
+        .fold(0, |mode, (i, _)| mode | (0o400 >> i));
+    Some(mode)
+}
+
 #[cfg(unix)]
 fn format_mode(mode: u32) -> String {
     let flags = [
@@ -102,84 +269,995 @@ fn format_mode(mode: u32) -> String {
     flags.iter().map(|&(bit, ch)| if mode & bit != 0 { ch } else { '-' }).collect()
 }
 
+impl ExportDocument {
+    /// Wraps `node` in an envelope stamped with the current time and kree
+    /// version.
+    pub fn new(node: &TreeNode, root_path: Option<&Path>, options: ScanOptions) -> Self {
+        ExportDocument {
+            schema_version: SCHEMA_VERSION,
+            kree_version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: format_iso_time(&SystemTime::now()),
+            root_path: root_path.map(|p| p.display().to_string()),
+            options,
+            tree: to_export_node(node),
+        }
+    }
+}
+
 /// Exports the tree as JSON string.
-pub fn export_json(node: &TreeNode) -> String {
-    let export = to_export_node(node);
-    serde_json::to_string_pretty(&export).unwrap_or_else(|e| format!("Error: {e}"))
+pub fn export_json(document: &ExportDocument) -> String {
+    serde_json::to_string_pretty(document).unwrap_or_else(|e| format!("Error: {e}"))
+}
+
+/// Reads a tree previously written by `export_json` or `export_yaml`.
+///
+/// Exports from before the envelope was introduced, which hold the bare
+/// root node, are accepted as well.
+pub fn import_tree(contents: &str) -> Result<ExportNode, String> {
+    import_document(contents).map(|(tree, _)| tree)
+}
+
+/// Like `import_tree`, but also returns the scan options of an envelope
+/// export (`None` for a bare root node).
+pub fn import_document(contents: &str) -> Result<(ExportNode, Option<ScanOptions>), String> {
+    let split = |document: ExportDocument| (document.tree, Some(document.options));
+    let bare = |tree: ExportNode| (tree, None);
+    if contents.trim_start().starts_with('{') {
+        let value: serde_json::Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+        match value.get("schema_version") {
+            Some(version) => {
+                check_schema_version(version.as_u64())?;
+                serde_json::from_value(value)
+                    .map(split)
+                    .map_err(|e| e.to_string())
+            }
+            None => serde_json::from_value(value)
+                .map(bare)
+                .map_err(|e| e.to_string()),
+        }
+    } else {
+        let value: serde_yml::Value = serde_yml::from_str(contents).map_err(|e| e.to_string())?;
+        match value.get("schema_version") {
+            Some(version) => {
+                check_schema_version(version.as_u64())?;
+                serde_yml::from_value(value)
+                    .map(split)
+                    .map_err(|e| e.to_string())
+            }
+            None => serde_yml::from_value(value)
+                .map(bare)
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+fn check_schema_version(version: Option<u64>) -> Result<(), String> {
+    match version {
+        Some(version) if version <= u64::from(SCHEMA_VERSION) => Ok(()),
+        Some(version) => Err(format!(
+            "schema_version {version} is newer than this kree supports ({SCHEMA_VERSION})"
+        )),
+        None => Err("schema_version is not a number".to_string()),
+    }
 }
 
 /// Exports the tree as YAML string.
-pub fn export_yaml(node: &TreeNode) -> String {
+pub fn export_yaml(document: &ExportDocument) -> String {
+    serde_yml::to_string(document).unwrap_or_else(|e| format!("Error: {e}"))
+}
+
+/// Exports the tree in the XML format of `tree -X`.
+///
+/// With `--long`, entries carry the `mode`, `prot`, `user`, `size` and
+/// `time` attributes that `tree -X -pusD` writes; `time` is an ISO 8601
+/// timestamp.
+pub fn export_xml(node: &TreeNode) -> String {
     let export = to_export_node(node);
-    serde_yml::to_string(&export).unwrap_or_else(|e| format!("Error: {e}"))
+    let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<tree>\n");
+    write_xml(&export, 1, &mut output);
+    let (dirs, files) = count_entries(&export);
+    output.push_str(&format!(
+        "  <report>\n    <directories>{dirs}</directories>\n    <files>{files}</files>\n  \
+         </report>\n</tree>\n"
+    ));
+    output
+}
+
+fn write_xml(node: &ExportNode, depth: usize, output: &mut String) {
+    let indent = "  ".repeat(depth);
+    // Like `tree -l`, a followed symlink is a <link> holding its target's entries
+    let element = match node.node_type {
+        _ if node.symlink_target.is_some() => "link",
+        NodeType::Directory => "directory",
+        NodeType::File => "file",
+        NodeType::Symlink => "link",
+    };
+
+    let mut attrs = format!("name=\"{}\"", escape_markup(&node.name));
+    if let Some(ref target) = node.symlink_target {
+        attrs.push_str(&format!(" target=\"{}\"", escape_markup(target)));
+    }
+    if let Some(ref permissions) = node.permissions
+        && let Some(mode) = parse_mode(permissions)
+    {
+        let kind = match element {
+            "directory" => 'd',
+            "link" => 'l',
+            _ => '-',
+        };
+        attrs.push_str(&format!(
+            " mode=\"{mode:04o}\" prot=\"{kind}{permissions}\""
+        ));
+    }
+    if let Some(ref owner) = node.owner {
+        attrs.push_str(&format!(" user=\"{}\"", escape_markup(owner)));
+    }
+    if let Some(size) = node.size {
+        attrs.push_str(&format!(" size=\"{size}\""));
+    }
+    if let Some(ref modified) = node.modified {
+        attrs.push_str(&format!(" time=\"{modified}\""));
+    }
+    if let Some(loc) = node.loc {
+        attrs.push_str(&format!(
+            " code=\"{}\" comment=\"{}\" blank=\"{}\"",
+            loc.code, loc.comment, loc.blank
+        ));
+    }
+
+    if node.node_type == NodeType::Directory {
+        output.push_str(&format!("{indent}<{element} {attrs}>\n"));
+        for child in &node.children {
+            write_xml(child, depth + 1, output);
+        }
+        output.push_str(&format!("{indent}</{element}>\n"));
+    } else {
+        output.push_str(&format!("{indent}<{element} {attrs}></{element}>\n"));
+    }
+}
+
+/// Exports the tree as CSV, one row per entry with its depth below the
+/// root. The metadata columns are empty unless `--long` collected them,
+/// `hash` is empty without `--hash` and the line counts without `--loc`.
+pub fn export_csv(node: &TreeNode) -> String {
+    let mut output =
+        String::from("path,type,depth,size,modified,mode,owner,hash,code,comment,blank\n");
+    write_csv(&to_export_node(node), 0, &mut output);
+    output
+}
+
+fn write_csv(node: &ExportNode, depth: usize, output: &mut String) {
+    let fields = [
+        csv_field(&node.path),
+        node.node_type.as_str().to_string(),
+        depth.to_string(),
+        node.size.map(|s| s.to_string()).unwrap_or_default(),
+        node.modified.clone().unwrap_or_default(),
+        node.permissions.clone().unwrap_or_default(),
+        csv_field(node.owner.as_deref().unwrap_or_default()),
+        node.hash.clone().unwrap_or_default(),
+        node.loc.map(|l| l.code.to_string()).unwrap_or_default(),
+        node.loc.map(|l| l.comment.to_string()).unwrap_or_default(),
+        node.loc.map(|l| l.blank.to_string()).unwrap_or_default(),
+    ];
+    output.push_str(&fields.join(","));
+    output.push('\n');
+    for child in &node.children {
+        write_csv(child, depth + 1, output);
+    }
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One line of the NDJSON export: an entry without its children, linked to
+/// its parent by id.
+#[derive(Serialize)]
+struct FlatNode<'a> {
+    id: usize,
+    parent: Option<usize>,
+    depth: usize,
+    #[serde(flatten)]
+    node: &'a ExportNode,
+}
+
+/// Exports the tree as newline-delimited JSON, one object per entry in
+/// depth-first order. Entries are numbered from 0 (the root) and refer to
+/// their directory through `parent`; their `children` array is always
+/// empty.
+pub fn export_ndjson(node: &TreeNode) -> String {
+    let mut output = String::new();
+    write_ndjson(to_export_node(node), None, 0, &mut 0, &mut output);
+    output
+}
+
+fn write_ndjson(
+    mut node: ExportNode,
+    parent: Option<usize>,
+    depth: usize,
+    next_id: &mut usize,
+    output: &mut String,
+) {
+    let id = *next_id;
+    *next_id += 1;
+    let children = std::mem::take(&mut node.children);
+    let flat = FlatNode {
+        id,
+        parent,
+        depth,
+        node: &node,
+    };
+    output.push_str(&serde_json::to_string(&flat).unwrap_or_else(|e| format!("Error: {e}")));
+    output.push('\n');
+    for child in children {
+        write_ndjson(child, Some(id), depth + 1, next_id, output);
+    }
+}
+
+/// Counts directories and files below the root, as in the tree summary.
+pub(crate) fn count_entries(node: &ExportNode) -> (usize, usize) {
+    node.children.iter().fold((0, 0), |(dirs, files), child| {
+        let (d, f) = count_entries(child);
+        if child.node_type == NodeType::Directory {
+            (dirs + child.merged.len().max(1) + d, files + f)
+        } else {
+            (dirs + d, files + 1 + f)
+        }
+    })
+}
+
+/// Escapes text for use in XML and HTML content and attribute values.
+pub(crate) fn escape_markup(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Layout of the Markdown export (`--md-style`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum MdStyle {
+    /// Nested bullet list.
+    #[default]
+    List,
+    /// Fenced box-drawing tree, as printed in the terminal.
+    Tree,
+    /// Table with one row per entry and metadata and note columns.
+    Table,
 }
 
 /// Exports the tree as a Markdown indented list.
-pub fn export_markdown(node: &TreeNode) -> String {
+///
+/// With `links`, entries link to their path relative to the root so the
+/// list is clickable on GitHub. Metadata collected by `--long` follows the
+/// name as inline code, and notes are appended as ` — note`.
+pub fn export_markdown(node: &TreeNode, links: bool) -> String {
     let mut output = String::new();
-    output.push_str(&format!("- **{}**\n", node.name));
+    write_markdown(node, &node.path, 0, links, &mut output);
+    output
+}
+
+fn write_markdown(node: &TreeNode, root: &Path, depth: usize, links: bool, output: &mut String) {
+    let indent = "  ".repeat(depth);
+    let name = markdown_name(&node.name, node, root, links);
+    let metadata = match node.metadata {
+        Some(ref meta) if !metadata_columns(meta).is_empty() => {
+            format!(" `{}`", metadata_columns(meta).join("  "))
+        }
+        _ => String::new(),
+    };
+    let note = markdown_note(node);
+    output.push_str(&format!("{indent}- {name}{metadata}{note}\n"));
     for child in &node.children {
-        write_markdown(child, 1, &mut output);
+        write_markdown(child, root, depth + 1, links, output);
+    }
+}
+
+/// Exports the tree as a Markdown table with one row per entry, named by its
+/// path relative to the root. Metadata and note columns are only added when
+/// some entry has them.
+pub fn export_markdown_table(node: &TreeNode, links: bool) -> String {
+    let mut rows = Vec::new();
+    collect_rows(node, &mut rows);
+    let has_metadata = rows.iter().any(|row| row.metadata.is_some());
+    let has_notes = rows.iter().any(|row| row.note.is_some());
+
+    let mut header = vec!["Name", "Type"];
+    if has_metadata {
+        #[cfg(unix)]
+        header.extend(["Permissions", "Owner"]);
+        header.extend(["Size", "Modified"]);
+    }
+    if has_notes {
+        header.push("Note");
+    }
+
+    let mut output = format!("| {} |\n", header.join(" | "));
+    let separators: Vec<&str> = header
+        .iter()
+        .map(|&column| if column == "Size" { "---:" } else { "---" })
+        .collect();
+    output.push_str(&format!("| {} |\n", separators.join(" | ")));
+
+    for row in rows {
+        let relative = row.path.strip_prefix(&node.path).unwrap_or(&row.path);
+        let text = if relative.as_os_str().is_empty() {
+            row.name.clone()
+        } else {
+            let components: Vec<_> = relative.components().map(|c| c.as_os_str()).collect();
+            components.join("/".as_ref()).to_string_lossy().into_owned()
+        };
+        let mut cells = vec![
+            markdown_name(&text, row, &node.path, links),
+            node_type(row).as_str().to_string(),
+        ];
+        if has_metadata {
+            let meta = row.metadata.as_ref();
+            #[cfg(unix)]
+            {
+                cells.push(
+                    meta.and_then(|m| m.mode)
+                        .map(format_mode)
+                        .unwrap_or_default(),
+                );
+                cells.push(meta.and_then(|m| m.owner.clone()).unwrap_or_default());
+            }
+            cells.push(
+                meta.and_then(|m| m.size)
+                    .map(format_size)
+                    .unwrap_or_default(),
+            );
+            cells.push(
+                meta.and_then(|m| m.modified.as_ref())
+                    .map(format_time)
+                    .unwrap_or_default(),
+            );
+        }
+        if has_notes {
+            cells.push(row.note.as_deref().unwrap_or_default().replace('|', "\\|"));
+        }
+        output.push_str(&format!("| {} |\n", cells.join(" | ")));
     }
     output
 }
 
-fn write_markdown(node: &TreeNode, depth: usize, output: &mut String) {
+fn collect_rows<'a>(node: &'a TreeNode, rows: &mut Vec<&'a TreeNode>) {
+    rows.push(node);
+    for child in &node.children {
+        collect_rows(child, rows);
+    }
+}
+
+/// Exports the tree as a fenced block holding the box-drawing tree of the
+/// terminal renderer, without ANSI codes.
+pub fn export_markdown_tree(
+    node: &TreeNode,
+    styler: &Styler,
+    icon_map: Option<&IconMap>,
+    opts: &RenderOptions,
+) -> String {
+    let opts = RenderOptions {
+        plain: true,
+        ..opts.clone()
+    };
+    format!(
+        "```text\n{}```\n",
+        format_tree(node, styler, icon_map, &opts)
+    )
+}
+
+/// Formats an entry name: bold with a trailing `/` for directories (the
+/// root is only bold), and linked to its path below `root` with `links`.
+fn markdown_name(text: &str, node: &TreeNode, root: &Path, links: bool) -> String {
+    let is_root = node.path == root;
+    let mut name = markdown_escape(text);
+    if links
+        && !is_root
+        && let Ok(relative) = node.path.strip_prefix(root)
+    {
+        name = format!("[{name}]({})", relative_link(relative));
+    }
+    match (node.is_dir, is_root) {
+        (_, true) => format!("**{name}**"),
+        (true, false) => format!("**{name}**/"),
+        (false, false) => name,
+    }
+}
+
+/// Builds a relative URL from a path, joining components with `/` and
+/// percent-encoding everything but unreserved characters.
+fn relative_link(path: &Path) -> String {
+    let mut link = String::new();
+    for (i, component) in path.components().enumerate() {
+        if i > 0 {
+            link.push('/');
+        }
+        for byte in component.as_os_str().to_string_lossy().bytes() {
+            if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+                link.push(byte as char);
+            } else {
+                link.push_str(&format!("%{byte:02X}"));
+            }
+        }
+    }
+    link
+}
+
+/// Escapes the characters Markdown would read as emphasis, links, inline
+/// code, HTML or table separators (`__init__.py` would otherwise be bold).
+fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '[' | ']' | '`' | '<' | '>' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn markdown_note(node: &TreeNode) -> String {
+    match node.note {
+        Some(ref note) => format!(" — {note}"),
+        None => String::new(),
+    }
+}
+
+/// Exports the tree as a LaTeX `\dirtree` (from the `dirtree` package).
+///
+/// Directory names are bold and notes become `\DTcomment`s.
+pub fn export_latex(node: &TreeNode) -> String {
+    let mut output = String::from("\\dirtree{%\n");
+    write_latex(node, 1, &mut output);
+    output.push_str("}\n");
+    output
+}
+
+fn write_latex(node: &TreeNode, level: usize, output: &mut String) {
+    let name = latex_escape(&node.name);
+    let name = match (node.is_dir, level) {
+        (true, 1) => format!("\\textbf{{{name}}}"),
+        (true, _) => format!("\\textbf{{{name}}}/"),
+        (false, _) => name,
+    };
+    let note = match node.note {
+        Some(ref note) => format!("\\DTcomment{{{}}}", latex_escape(note)),
+        None => String::new(),
+    };
+    output.push_str(&format!(".{level} {name}{note}.\n"));
+    for child in &node.children {
+        write_latex(child, level + 1, output);
+    }
+}
+
+/// Escapes the characters LaTeX treats specially in text mode.
+fn latex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Exports the tree as an Org-mode nested list.
+///
+/// With `--long`, each entry gets a `:PROPERTIES:` drawer holding its size,
+/// modification time, permissions and owner.
+pub fn export_org(node: &TreeNode) -> String {
+    let mut output = String::new();
+    write_org(node, 0, &mut output);
+    output
+}
+
+fn write_org(node: &TreeNode, depth: usize, output: &mut String) {
     let indent = "  ".repeat(depth);
-    if node.path.is_dir() {
-        output.push_str(&format!("{indent}- **{}**/\n", node.name));
-    } else {
-        output.push_str(&format!("{indent}- {}\n", node.name));
+    let name = org_name(&node.name, node.is_dir, depth == 0);
+    output.push_str(&format!("{indent}- {name}{}\n", markdown_note(node)));
+
+    let properties = org_properties(node);
+    if !properties.is_empty() {
+        output.push_str(&format!("{indent}  :PROPERTIES:\n"));
+        for (key, value) in properties {
+            output.push_str(&format!("{indent}  :{key}: {value}\n"));
+        }
+        output.push_str(&format!("{indent}  :END:\n"));
     }
+
     for child in &node.children {
-        write_markdown(child, depth + 1, output);
+        write_org(child, depth + 1, output);
     }
 }
 
+/// Formats a name for an Org list: directories in bold with a trailing `/`
+/// (emphasis must end before whitespace or punctuation, so the slash goes
+/// inside the markers).
+///
+/// Org has no escape character, so names holding emphasis markers
+/// (`__init__.py` would otherwise be underlined) are written as `=verbatim=`
+/// instead, or `~code~` if they contain `=`. The name itself is unchanged,
+/// so it can still be copied and searched for.
+fn org_name(name: &str, is_dir: bool, is_root: bool) -> String {
+    let slash = if is_dir && !is_root { "/" } else { "" };
+    match org_verbatim_marker(name) {
+        Some(marker) => format!("{marker}{name}{slash}{marker}"),
+        None if is_dir => format!("*{name}{slash}*"),
+        None => name.to_string(),
+    }
+}
+
+/// Returns the marker to quote `name` with, if it holds emphasis markers
+/// and can be quoted (verbatim text cannot start or end with whitespace).
+fn org_verbatim_marker(name: &str) -> Option<char> {
+    if !name.contains(['*', '/', '_', '=', '~', '+'])
+        || name.starts_with(char::is_whitespace)
+        || name.ends_with(char::is_whitespace)
+    {
+        return None;
+    }
+    ['=', '~']
+        .into_iter()
+        .find(|&marker| !name.contains(marker))
+}
+
+fn org_properties(node: &TreeNode) -> Vec<(&'static str, String)> {
+    let mut properties = Vec::new();
+    let Some(ref meta) = node.metadata else {
+        return properties;
+    };
+    if let Some(size) = meta.size {
+        properties.push(("SIZE", size.to_string()));
+    }
+    if let Some(ref modified) = meta.modified {
+        properties.push(("MODIFIED", format_iso_time(modified)));
+    }
+    #[cfg(unix)]
+    if let Some(mode) = meta.mode {
+        properties.push(("PERMISSIONS", format_mode(mode)));
+    }
+    #[cfg(unix)]
+    if let Some(ref owner) = meta.owner {
+        properties.push(("OWNER", owner.clone()));
+    }
+    properties
+}
+
+/// Exports the tree as an AsciiDoc nested list.
+pub fn export_asciidoc(node: &TreeNode) -> String {
+    let mut output = String::new();
+    write_asciidoc(node, 1, &mut output);
+    output
+}
+
+fn write_asciidoc(node: &TreeNode, depth: usize, output: &mut String) {
+    let marker = "*".repeat(depth);
+    let name = asciidoc_escape(&node.name);
+    let name = match (node.is_dir, depth) {
+        (true, 1) => format!("*{name}*"),
+        (true, _) => format!("*{name}*/"),
+        (false, _) => name,
+    };
+    output.push_str(&format!("{marker} {name}{}\n", markdown_note(node)));
+    for child in &node.children {
+        write_asciidoc(child, depth + 1, output);
+    }
+}
+
+/// Wraps a name holding AsciiDoc formatting marks, attribute references or
+/// macro brackets in a `pass:c[]` macro, which only escapes `<`, `>` and
+/// `&` (`__init__.py` would otherwise be italic).
+fn asciidoc_escape(text: &str) -> String {
+    let special = |c| {
+        matches!(
+            c,
+            '*' | '_' | '`' | '#' | '^' | '~' | '+' | '\\' | '{' | '}' | '[' | ']'
+        )
+    };
+    if !text.contains(special) {
+        return text.to_string();
+    }
+    format!("pass:c[{}]", text.replace(']', "\\]"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::tree::TreeNode;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     fn sample_tree() -> TreeNode {
         TreeNode {
             name: "root".to_string(),
             path: PathBuf::from("/tmp/root"),
-            is_symlink: false,
-            symlink_target: None,
-            metadata: None,
-            children: vec![
-                TreeNode {
-                    name: "file.txt".to_string(),
-                    path: PathBuf::from("/tmp/root/file.txt"),
-                    is_symlink: false,
-                    symlink_target: None,
-                    metadata: None,
-                    children: vec![],
-                },
-            ],
+            is_dir: true,
+            children: vec![TreeNode {
+                name: "file.txt".to_string(),
+                path: PathBuf::from("/tmp/root/file.txt"),
+                ..TreeNode::default()
+            }],
+            ..TreeNode::default()
         }
     }
 
+    fn document(tree: &TreeNode) -> ExportDocument {
+        ExportDocument::new(tree, None, ScanOptions::default())
+    }
+
     #[test]
     fn json_export_contains_name() {
-        let json = export_json(&sample_tree());
+        let json = export_json(&document(&sample_tree()));
         assert!(json.contains("\"name\": \"root\""));
         assert!(json.contains("\"name\": \"file.txt\""));
     }
 
     #[test]
     fn yaml_export_contains_name() {
-        let yaml = export_yaml(&sample_tree());
+        let yaml = export_yaml(&document(&sample_tree()));
         assert!(yaml.contains("name: root"));
         assert!(yaml.contains("name: file.txt"));
     }
 
+    #[test]
+    fn json_export_includes_file_kind() {
+        let mut tree = sample_tree();
+        tree.children[0].file_kind = crate::filetype::from_extension(Path::new("deploy.py"));
+        let json = export_json(&document(&tree));
+        assert!(json.contains("\"file_kind\": \"python\""));
+        assert!(json.contains("\"file_kind\": null"));
+    }
+
+    #[test]
+    fn json_export_marks_merged_nodes() {
+        let mut tree = sample_tree();
+        tree.children[0].merged = vec!["src".to_string(), "main".to_string()];
+        let value: serde_json::Value =
+            serde_json::from_str(&export_json(&document(&tree))).unwrap();
+        assert_eq!(
+            value["tree"]["children"][0]["merged"],
+            serde_json::json!(["src", "main"])
+        );
+        assert_eq!(value["tree"]["merged"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn json_export_round_trips() {
+        let mut tree = sample_tree();
+        tree.children[0].note = Some("sample data".to_string());
+        let imported = import_tree(&export_json(&document(&tree))).unwrap();
+        assert_eq!(imported.name, "root");
+        assert_eq!(imported.children[0].name, "file.txt");
+        assert_eq!(imported.children[0].node_type, NodeType::File);
+        assert_eq!(imported.children[0].note.as_deref(), Some("sample data"));
+        assert!(import_tree("{\"name\": \"x\"}").is_err());
+    }
+
+    #[test]
+    fn json_export_has_envelope() {
+        let options = ScanOptions {
+            depth: 3,
+            sort: "kind".to_string(),
+            gitignore: true,
+            ..ScanOptions::default()
+        };
+        let document = ExportDocument::new(&sample_tree(), Some(Path::new("/tmp/root")), options);
+        let value: serde_json::Value = serde_json::from_str(&export_json(&document)).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["kree_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(value["root_path"], "/tmp/root");
+        assert_eq!(value["options"]["depth"], 3);
+        assert_eq!(value["options"]["sort"], "kind");
+        assert!(parse_iso_time(value["generated_at"].as_str().unwrap()).is_some());
+        assert_eq!(value["tree"]["children"][0]["name"], "file.txt");
+    }
+
+    #[test]
+    fn json_export_matches_schema_fields() {
+        let schema: serde_json::Value = serde_json::from_str(EXPORT_SCHEMA).unwrap();
+        assert_eq!(
+            schema["properties"]["schema_version"]["const"],
+            SCHEMA_VERSION
+        );
+        let required = |def: &serde_json::Value| -> Vec<String> {
+            def["required"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|key| key.as_str().unwrap().to_string())
+                .collect()
+        };
+        let keys = |value: &serde_json::Value| -> Vec<String> {
+            value.as_object().unwrap().keys().cloned().collect()
+        };
+
+        // Every field is present, null or not, on every platform
+        let value: serde_json::Value =
+            serde_json::from_str(&export_json(&document(&sample_tree()))).unwrap();
+        let mut expected = required(&schema);
+        expected.sort();
+        assert_eq!(keys(&value), expected);
+        let mut expected = required(&schema["$defs"]["options"]);
+        expected.sort();
+        assert_eq!(keys(&value["options"]), expected);
+        let mut expected = required(&schema["$defs"]["node"]);
+        expected.sort();
+        assert_eq!(keys(&value["tree"]), expected);
+        assert_eq!(keys(&value["tree"]["children"][0]), expected);
+        assert!(value["tree"]["children"][0]["permissions"].is_null());
+        assert_eq!(
+            value["tree"]["children"][0]["children"],
+            serde_json::json!([])
+        );
+    }
+
+    #[test]
+    fn import_accepts_bare_trees_and_rejects_newer_schemas() {
+        let bare = import_tree("{\"name\": \"x\", \"path\": \"x\", \"type\": \"file\"}");
+        assert_eq!(bare.unwrap().name, "x");
+        let yaml = "schema_version: 1\nkree_version: 1.0.0\ngenerated_at: \
+                    2024-01-01T00:00:00Z\nroot_path: null\noptions:\n  depth: 1\n  \
+                    sort: kind\n  all: false\n  gitignore: true\n  dirs_only: false\n  \
+                    extensions: []\n  long: false\n  detect: false\n  compact: false\n\
+                    tree:\n  name: x\n  path: x\n  type: directory\n";
+        assert_eq!(import_tree(yaml).unwrap().node_type, NodeType::Directory);
+        let newer = import_tree("{\"schema_version\": 99, \"tree\": {}}");
+        assert!(matches!(newer, Err(e) if e.contains("schema_version 99")));
+    }
+
+    #[test]
+    fn yaml_export_round_trips_metadata() {
+        let mut tree = sample_tree();
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        tree.children[0].metadata = Some(NodeMetadata {
+            size: Some(42),
+            modified: Some(modified),
+            #[cfg(unix)]
+            mode: Some(0o754),
+            #[cfg(unix)]
+            owner: Some("kree".to_string()),
+        });
+        tree.children[0].file_kind = crate::filetype::from_extension(Path::new("x.py"));
+
+        let loaded = from_export_node(import_tree(&export_yaml(&document(&tree))).unwrap());
+        assert!(loaded.is_dir);
+        let file = &loaded.children[0];
+        assert!(!file.is_dir);
+        assert_eq!(file.file_kind.map(|k| k.name), Some("python"));
+        let meta = file.metadata.as_ref().unwrap();
+        assert_eq!(meta.size, Some(42));
+        assert_eq!(meta.modified, Some(modified));
+        #[cfg(unix)]
+        assert_eq!(meta.mode, Some(0o754));
+    }
+
+    #[test]
+    fn iso_time_round_trips() {
+        for secs in [0, 951_782_400, 1_700_000_000, 4_102_444_799] {
+            let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+            assert_eq!(parse_iso_time(&format_iso_time(&time)), Some(time));
+        }
+        assert_eq!(parse_iso_time("yesterday"), None);
+    }
+
     #[test]
     fn markdown_export_structure() {
-        let md = export_markdown(&sample_tree());
+        let md = export_markdown(&sample_tree(), false);
         assert!(md.contains("- **root**"));
         assert!(md.contains("  - file.txt"));
     }
+
+    #[test]
+    fn markdown_export_includes_notes() {
+        let mut tree = sample_tree();
+        tree.children[0].note = Some("sample data".to_string());
+        let md = export_markdown(&tree, false);
+        assert!(md.contains("  - file.txt — sample data\n"));
+    }
+
+    #[test]
+    fn xml_export_matches_tree_format() {
+        let xml = export_xml(&sample_tree());
+        assert_eq!(
+            xml,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<tree>\n  <directory name=\"root\">\n    \
+             <file name=\"file.txt\"></file>\n  </directory>\n  <report>\n    \
+             <directories>0</directories>\n    <files>1</files>\n  </report>\n</tree>\n"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn xml_export_metadata_attributes() {
+        let mut tree = sample_tree();
+        tree.children[0].metadata = Some(NodeMetadata {
+            size: Some(42),
+            mode: Some(0o644),
+            owner: Some("kree".to_string()),
+            ..NodeMetadata::default()
+        });
+        let xml = export_xml(&tree);
+        assert!(xml.contains(
+            "<file name=\"file.txt\" mode=\"0644\" prot=\"-rw-r--r--\" user=\"kree\" size=\"42\">"
+        ));
+    }
+
+    #[test]
+    fn csv_export_rows_and_quoting() {
+        let mut tree = sample_tree();
+        tree.children[0].path = PathBuf::from("/tmp/root/a,\"b\".txt");
+        let csv = export_csv(&tree);
+        assert_eq!(
+            csv,
+            "path,type,depth,size,modified,mode,owner,hash,code,comment,blank\n\
+             /tmp/root,directory,0,,,,,,,,\n\
+             \"/tmp/root/a,\"\"b\"\".txt\",file,1,,,,,,,,\n"
+        );
+    }
+
+    #[test]
+    fn ndjson_export_links_parents() {
+        let ndjson = export_ndjson(&sample_tree());
+        let lines: Vec<serde_json::Value> = ndjson
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["parent"], serde_json::Value::Null);
+        assert_eq!(lines[1]["id"], 1);
+        assert_eq!(lines[1]["parent"], 0);
+        assert_eq!(lines[1]["depth"], 1);
+        assert_eq!(lines[1]["type"], "file");
+        assert_eq!(lines[0]["children"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn latex_export_escapes_names() {
+        let mut tree = sample_tree();
+        tree.children[0].name = "50%_{draft}#1.tex".to_string();
+        tree.children[0].note = Some("R&D".to_string());
+        assert_eq!(
+            export_latex(&tree),
+            "\\dirtree{%\n.1 \\textbf{root}.\n\
+             .2 50\\%\\_\\{draft\\}\\#1.tex\\DTcomment{R\\&D}.\n}\n"
+        );
+        assert_eq!(
+            latex_escape("a\\b~c^d"),
+            "a\\textbackslash{}b\\textasciitilde{}c\\textasciicircum{}d"
+        );
+    }
+
+    #[test]
+    fn org_export_property_drawers() {
+        let mut tree = sample_tree();
+        assert_eq!(export_org(&tree), "- *root*\n  - file.txt\n");
+
+        tree.children[0].metadata = Some(NodeMetadata {
+            size: Some(42),
+            ..NodeMetadata::default()
+        });
+        assert_eq!(
+            export_org(&tree),
+            "- *root*\n  - file.txt\n    :PROPERTIES:\n    :SIZE: 42\n    :END:\n"
+        );
+    }
+
+    #[test]
+    fn asciidoc_export_structure() {
+        let mut tree = sample_tree();
+        tree.children.insert(
+            0,
+            TreeNode {
+                name: "src".to_string(),
+                is_dir: true,
+                note: Some("sources".to_string()),
+                ..TreeNode::default()
+            },
+        );
+        assert_eq!(
+            export_asciidoc(&tree),
+            "* *root*\n** *src*/ — sources\n** file.txt\n"
+        );
+    }
+
+    #[test]
+    fn org_and_asciidoc_escape_names() {
+        let mut tree = sample_tree();
+        tree.children = vec![TreeNode {
+            name: "pkg".to_string(),
+            is_dir: true,
+            children: vec![TreeNode {
+                name: "__init__.py".to_string(),
+                ..TreeNode::default()
+            }],
+            ..TreeNode::default()
+        }];
+        assert_eq!(
+            export_org(&tree),
+            "- *root*\n  - *pkg/*\n    - =__init__.py=\n"
+        );
+        assert_eq!(org_name("my_docs", true, false), "=my_docs/=");
+        assert_eq!(org_name("a=b+c", false, false), "~a=b+c~");
+        assert_eq!(
+            export_asciidoc(&tree),
+            "* *root*\n** *pkg*/\n*** pass:c[__init__.py]\n"
+        );
+        assert_eq!(asciidoc_escape("a[1].txt"), "pass:c[a[1\\].txt]");
+    }
+
+    #[test]
+    fn markdown_export_links_and_escapes() {
+        let mut tree = sample_tree();
+        tree.children[0].name = "__init__ (1).py".to_string();
+        tree.children[0].path = PathBuf::from("/tmp/root/__init__ (1).py");
+        assert_eq!(
+            export_markdown(&tree, true),
+            "- **root**\n  - [\\_\\_init\\_\\_ (1).py](__init__%20%281%29.py)\n"
+        );
+    }
+
+    #[test]
+    fn markdown_table_columns() {
+        let mut tree = sample_tree();
+        assert_eq!(
+            export_markdown_table(&tree, false),
+            "| Name | Type |\n| --- | --- |\n| **root** | directory |\n| file.txt | file |\n"
+        );
+
+        tree.children[0].note = Some("a | b".to_string());
+        tree.children[0].metadata = Some(NodeMetadata {
+            size: Some(2048),
+            ..NodeMetadata::default()
+        });
+        let table = export_markdown_table(&tree, true);
+        assert!(table.contains(" | Size | Modified | Note |\n"));
+        assert!(table.contains(" | ---: | --- | --- |\n"));
+        assert!(table.ends_with(" | 2.0K |  | a \\| b |\n"));
+        assert!(table.contains("| [file.txt](file.txt) | file | "));
+    }
+
+    #[test]
+    fn markdown_tree_is_fenced_without_ansi() {
+        use crate::render::build_color_map;
+        use crate::theme::UiTheme;
+        use std::collections::HashMap;
+
+        let styler = Styler::new(
+            build_color_map(&HashMap::new()),
+            None,
+            Vec::new(),
+            UiTheme::default(),
+        );
+        let colorize = colored::control::SHOULD_COLORIZE.should_colorize();
+        let md = export_markdown_tree(&sample_tree(), &styler, None, &RenderOptions::default());
+        assert_eq!(
+            md,
+            "```text\n└── root\n     └── file.txt\n\n0 directories, 1 files\n```\n"
+        );
+        // The process-wide color setting is left alone
+        assert_eq!(
+            colored::control::SHOULD_COLORIZE.should_colorize(),
+            colorize
+        );
+    }
 }
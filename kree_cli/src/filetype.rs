@@ -0,0 +1,297 @@
+//! Content-based file type detection.
+//!
+//! Identifies a file's kind from well-known file names, magic bytes and
+//! shebang lines, falling back to its extension. Detection is optional
+//! (`--detect`) because it reads the first bytes of every file.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Number of bytes read from the start of a file for detection.
+///
+/// Large enough to reach the `ustar` signature of tar archives at offset 257.
+const SNIFF_LEN: usize = 512;
+
+/// A detected file kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileKind {
+    /// Kind name, e.g. `python` or `png`.
+    pub name: &'static str,
+    /// Canonical extension, used to look up colors and icons.
+    pub ext: &'static str,
+    /// Other extensions of this kind.
+    aliases: &'static [&'static str],
+}
+
+impl FileKind {
+    const fn new(name: &'static str, ext: &'static str, aliases: &'static [&'static str]) -> Self {
+        FileKind { name, ext, aliases }
+    }
+
+    /// Returns true if `filter` (a lowercase extension or kind name) selects
+    /// this kind.
+    pub fn matches(&self, filter: &str) -> bool {
+        self.name == filter || self.ext == filter || self.aliases.contains(&filter)
+    }
+}
+
+const KINDS: &[FileKind] = &[
+    // Languages
+    FileKind::new("python", "py", &["pyw", "pyi"]),
+    FileKind::new("shell", "sh", &["bash", "zsh", "ksh", "fish"]),
+    FileKind::new("ruby", "rb", &["rake", "gemspec"]),
+    FileKind::new("perl", "pl", &["pm"]),
+    FileKind::new("javascript", "js", &["mjs", "cjs", "jsx"]),
+    FileKind::new("typescript", "ts", &["mts", "cts", "tsx"]),
+    FileKind::new("lua", "lua", &[]),
+    FileKind::new("php", "php", &[]),
+    FileKind::new("rust", "rs", &[]),
+    FileKind::new("go", "go", &[]),
+    FileKind::new("c", "c", &["h"]),
+    FileKind::new("cpp", "cpp", &["cc", "cxx", "hpp", "hh"]),
+    FileKind::new("java", "java", &[]),
+    // Build / config
+    FileKind::new("makefile", "mk", &["mak"]),
+    FileKind::new("dockerfile", "dockerfile", &[]),
+    FileKind::new("cmake", "cmake", &[]),
+    FileKind::new("toml", "toml", &[]),
+    FileKind::new("json", "json", &[]),
+    FileKind::new("yaml", "yaml", &["yml"]),
+    FileKind::new("ignore", "gitignore", &[]),
+    FileKind::new("dotenv", "env", &[]),
+    // Documents
+    FileKind::new("markdown", "md", &["markdown"]),
+    FileKind::new("html", "html", &["htm"]),
+    FileKind::new("css", "css", &[]),
+    FileKind::new("xml", "xml", &[]),
+    FileKind::new("pdf", "pdf", &[]),
+    // Images
+    FileKind::new("png", "png", &[]),
+    FileKind::new("jpeg", "jpg", &["jpeg"]),
+    FileKind::new("gif", "gif", &[]),
+    FileKind::new("webp", "webp", &[]),
+    // Archives
+    FileKind::new("zip", "zip", &["jar"]),
+    FileKind::new("gzip", "gz", &["tgz"]),
+    FileKind::new("bzip2", "bz2", &[]),
+    FileKind::new("xz", "xz", &[]),
+    FileKind::new("7z", "7z", &[]),
+    FileKind::new("tar", "tar", &[]),
+    // Binaries
+    FileKind::new("elf", "elf", &["so", "o"]),
+    FileKind::new("macho", "macho", &["dylib"]),
+    FileKind::new("wasm", "wasm", &[]),
+    FileKind::new("sqlite", "sqlite", &["db", "sqlite3"]),
+];
+
+/// Well-known file names (lowercase) and their kinds.
+const NAMES: &[(&str, &str)] = &[
+    ("makefile", "makefile"),
+    ("gnumakefile", "makefile"),
+    ("dockerfile", "dockerfile"),
+    ("containerfile", "dockerfile"),
+    ("cmakelists.txt", "cmake"),
+    ("gemfile", "ruby"),
+    ("rakefile", "ruby"),
+    ("vagrantfile", "ruby"),
+    ("cargo.lock", "toml"),
+    ("pipfile", "toml"),
+    (".kreerc", "toml"),
+    (".bashrc", "shell"),
+    (".bash_profile", "shell"),
+    (".bash_aliases", "shell"),
+    (".bash_logout", "shell"),
+    (".profile", "shell"),
+    (".zshrc", "shell"),
+    (".zprofile", "shell"),
+    (".zshenv", "shell"),
+    (".gitignore", "ignore"),
+    (".dockerignore", "ignore"),
+    (".kreeignore", "ignore"),
+    (".env", "dotenv"),
+];
+
+/// Magic byte signatures at a given offset.
+const MAGIC: &[(usize, &[u8], &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "png"),
+    (0, b"\xff\xd8\xff", "jpeg"),
+    (0, b"GIF8", "gif"),
+    (0, b"%PDF-", "pdf"),
+    (0, b"PK\x03\x04", "zip"),
+    (0, b"\x1f\x8b", "gzip"),
+    (0, b"BZh", "bzip2"),
+    (0, b"\xfd7zXZ\x00", "xz"),
+    (0, b"7z\xbc\xaf\x27\x1c", "7z"),
+    (0, b"\x7fELF", "elf"),
+    (0, b"\xcf\xfa\xed\xfe", "macho"),
+    (0, b"\xce\xfa\xed\xfe", "macho"),
+    (0, b"\x00asm", "wasm"),
+    (0, b"SQLite format 3\x00", "sqlite"),
+    (257, b"ustar", "tar"),
+];
+
+/// Shebang interpreters (with version suffixes stripped) and their kinds.
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("python", "python"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("zsh", "shell"),
+    ("dash", "shell"),
+    ("ksh", "shell"),
+    ("ash", "shell"),
+    ("fish", "shell"),
+    ("ruby", "ruby"),
+    ("perl", "perl"),
+    ("node", "javascript"),
+    ("nodejs", "javascript"),
+    ("bun", "javascript"),
+    ("deno", "typescript"),
+    ("ts-node", "typescript"),
+    ("lua", "lua"),
+    ("luajit", "lua"),
+    ("php", "php"),
+];
+
+/// Returns the kind called `name` (e.g. `python`), as stored in exports.
+pub fn kind_named(name: &str) -> Option<FileKind> {
+    KINDS.iter().find(|k| k.name == name).copied()
+}
+
+/// Detects the kind of the file at `path`.
+///
+/// Checks, in order: well-known file names, magic bytes, a shebang line and
+/// finally the extension. Returns `None` for directories, unknown files and
+/// special files (FIFOs, sockets, devices), which are never opened: reading
+/// a FIFO blocks until something writes to it.
+pub fn detect(path: &Path) -> Option<FileKind> {
+    if !path.metadata().ok()?.is_file() {
+        return None;
+    }
+
+    let filename = path.file_name()?.to_string_lossy().to_lowercase();
+    if let Some(&(_, kind)) = NAMES.iter().find(|(name, _)| *name == filename) {
+        return kind_named(kind);
+    }
+
+    if let Some(kind) = sniff(path).as_deref().and_then(detect_content) {
+        return Some(kind);
+    }
+
+    from_extension(path)
+}
+
+/// Returns the kind matching the extension of `path`, if any.
+pub fn from_extension(path: &Path) -> Option<FileKind> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    KINDS
+        .iter()
+        .find(|k| k.ext == ext || k.aliases.contains(&ext.as_str()))
+        .copied()
+}
+
+/// Reads the first `SNIFF_LEN` bytes of a file.
+fn sniff(path: &Path) -> Option<Vec<u8>> {
+    let file = File::open(path).ok()?;
+    let mut buf = Vec::with_capacity(SNIFF_LEN);
+    file.take(SNIFF_LEN as u64).read_to_end(&mut buf).ok()?;
+    Some(buf)
+}
+
+/// Returns true if `head`, the start of a file, looks binary: it has a
+/// null byte within its first `SNIFF_LEN` bytes.
+pub fn is_binary(head: &[u8]) -> bool {
+    head[..head.len().min(SNIFF_LEN)].contains(&0)
+}
+
+/// Detects a kind from magic bytes or a shebang line.
+fn detect_content(head: &[u8]) -> Option<FileKind> {
+    if let Some(&(_, _, kind)) = MAGIC
+        .iter()
+        .find(|(offset, magic, _)| head.get(*offset..*offset + magic.len()) == Some(*magic))
+    {
+        return kind_named(kind);
+    }
+
+    let line = head.strip_prefix(b"#!")?;
+    let line = line.split(|&b| b == b'\n').next()?;
+    let interpreter = shebang_interpreter(&String::from_utf8_lossy(line))?;
+    INTERPRETERS
+        .iter()
+        .find(|(name, _)| *name == interpreter)
+        .and_then(|&(_, kind)| kind_named(kind))
+}
+
+/// Extracts the interpreter name from a shebang line (without `#!`).
+///
+/// Handles `/usr/bin/env [-S] [VAR=value] python3` and strips version
+/// suffixes such as `3.12`.
+fn shebang_interpreter(line: &str) -> Option<String> {
+    let mut words = line.split_whitespace();
+    let program = words.next()?.rsplit('/').next()?;
+    let program = if program == "env" {
+        words.find(|w| !w.starts_with('-') && !w.contains('='))?
+    } else {
+        program
+    };
+    let name = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn binary_heads() {
+        assert!(is_binary(b"\x7fELF\x02\x01\x01\0"));
+        assert!(!is_binary("fn main() {}\n// café\n".as_bytes()));
+        let mut late_null = vec![b'a'; SNIFF_LEN];
+        late_null.push(0);
+        assert!(!is_binary(&late_null));
+    }
+
+    #[test]
+    fn shebang_with_env() {
+        assert_eq!(
+            shebang_interpreter("/usr/bin/env -S python3.12 -u").as_deref(),
+            Some("python")
+        );
+        assert_eq!(shebang_interpreter("/bin/bash -e").as_deref(), Some("bash"));
+    }
+
+    #[test]
+    fn extensionless_script() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("deploy");
+        fs::write(&path, "#!/usr/bin/env python\nprint('hi')\n").unwrap();
+        assert_eq!(detect(&path).map(|k| k.name), Some("python"));
+    }
+
+    #[test]
+    fn magic_bytes_beat_extension() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("photo.txt");
+        fs::write(&path, b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+        assert_eq!(detect(&path).map(|k| k.ext), Some("png"));
+    }
+
+    #[test]
+    fn well_known_names() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("Makefile");
+        fs::write(&path, "all:\n").unwrap();
+        assert_eq!(detect(&path).map(|k| k.name), Some("makefile"));
+        assert_eq!(detect(dir.path()), None);
+    }
+
+    #[test]
+    fn kind_matches_name_and_extensions() {
+        let kind = kind_named("yaml").unwrap();
+        assert!(kind.matches("yaml"));
+        assert!(kind.matches("yml"));
+        assert!(!kind.matches("json"));
+    }
+}
@@ -0,0 +1,148 @@
+//! Content digests of files (`--hash`, `--verify`).
+//!
+//! Files are read in fixed-size chunks so large files are hashed without
+//! loading them into memory. Digests are written as lowercase hex, the
+//! format of `sha256sum` and `b3sum`.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use clap::ValueEnum;
+use sha2::{Digest, Sha256};
+
+/// Size of the chunks read while hashing.
+const CHUNK_LEN: usize = 64 * 1024;
+
+/// Digest algorithm used by `--hash`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum HashAlgorithm {
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// Returns the name used on the command line and in exports.
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Parses a name written by `name`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        HashAlgorithm::from_str(name, true).ok()
+    }
+}
+
+/// Returns the hex digest of the file at `path`, or `None` if it cannot be
+/// read or is not a regular file.
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> Option<String> {
+    hash_reader(open_regular(path)?, algorithm)
+}
+
+/// Returns the hex digest of the first `len` bytes of the file at `path`.
+pub fn hash_head(path: &Path, len: u64, algorithm: HashAlgorithm) -> Option<String> {
+    hash_reader(open_regular(path)?.take(len), algorithm)
+}
+
+/// Opens `path` if it is a regular file. Opening a FIFO would block until
+/// something writes to it.
+fn open_regular(path: &Path) -> Option<File> {
+    if !path.metadata().ok()?.is_file() {
+        return None;
+    }
+    File::open(path).ok()
+}
+
+fn hash_reader(mut reader: impl Read, algorithm: HashAlgorithm) -> Option<String> {
+    let mut buf = vec![0u8; CHUNK_LEN];
+    match algorithm {
+        HashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            read_chunks(&mut reader, &mut buf, |chunk| hasher.update(chunk))?;
+            Some(format!("{:x}", hasher.finalize()))
+        }
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            read_chunks(&mut reader, &mut buf, |chunk| {
+                hasher.update(chunk);
+            })?;
+            Some(hasher.finalize().to_hex().to_string())
+        }
+    }
+}
+
+/// Feeds `reader` to `update` chunk by chunk until the end.
+fn read_chunks(
+    reader: &mut impl Read,
+    buf: &mut [u8],
+    mut update: impl FnMut(&[u8]),
+) -> Option<()> {
+    loop {
+        match reader.read(buf) {
+            Ok(0) => return Some(()),
+            Ok(n) => update(&buf[..n]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(_) => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn known_digests() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("abc.txt");
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            hash_file(&path, HashAlgorithm::Sha256).as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(
+            hash_file(&path, HashAlgorithm::Blake3).as_deref(),
+            Some("6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85")
+        );
+        assert_eq!(hash_file(dir.path(), HashAlgorithm::Sha256), None);
+    }
+
+    #[test]
+    fn hashes_files_larger_than_a_chunk() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("big.bin");
+        let contents = vec![7u8; CHUNK_LEN * 2 + 1];
+        fs::write(&path, &contents).unwrap();
+        assert_eq!(
+            hash_file(&path, HashAlgorithm::Blake3),
+            Some(blake3::hash(&contents).to_hex().to_string())
+        );
+    }
+
+    #[test]
+    fn head_digest_covers_a_prefix() {
+        let dir = tempdir().unwrap();
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        fs::write(&a, "same start, then a").unwrap();
+        fs::write(&b, "same start, then b").unwrap();
+        let head = |path| hash_head(path, 10, HashAlgorithm::Sha256);
+        assert_eq!(head(&a), head(&b));
+        assert_ne!(
+            hash_file(&a, HashAlgorithm::Sha256),
+            hash_file(&b, HashAlgorithm::Sha256)
+        );
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+            assert_eq!(HashAlgorithm::from_name(algorithm.name()), Some(algorithm));
+        }
+        assert_eq!(HashAlgorithm::from_name("md5"), None);
+    }
+}
@@ -0,0 +1,205 @@
+//! Heatmap coloring.
+//!
+//! Replaces extension colors with a cold-to-hot gradient driven by each
+//! entry's modification time, file size or depth (`--color-by`).
+
+use std::time::SystemTime;
+
+use clap::ValueEnum;
+
+use crate::style::StyleColor;
+use crate::tree::TreeNode;
+
+/// What the heatmap gradient represents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorBy {
+    /// Recently modified entries are hot, stale ones faded.
+    Mtime,
+    /// Larger files are hotter.
+    Size,
+    /// Shallow entries are hot, deeply nested ones faded.
+    Depth,
+}
+
+impl ColorBy {
+    /// Returns true if this mode needs file metadata to be collected.
+    pub fn needs_metadata(self) -> bool {
+        matches!(self, ColorBy::Mtime | ColorBy::Size)
+    }
+}
+
+/// Gradient stops, from cold (faded) to hot (bright).
+const GRADIENT: &[(u8, u8, u8)] = &[
+    (88, 88, 98),
+    (70, 130, 180),
+    (80, 200, 120),
+    (240, 200, 60),
+    (255, 90, 50),
+];
+
+/// Value ranges of a tree, used to place each entry on the gradient.
+#[derive(Clone, Debug)]
+pub struct Heatmap {
+    mode: ColorBy,
+    /// Most recent modification time in the tree.
+    newest: Option<SystemTime>,
+    /// Age of the oldest entry relative to `newest`, in seconds.
+    max_age: u64,
+    /// Size of the largest file, in bytes.
+    max_size: u64,
+    /// Deepest level in the tree (root is 0).
+    max_depth: usize,
+}
+
+impl Heatmap {
+    /// Scans `root` to find the range of values for `mode`.
+    pub fn new(mode: ColorBy, root: &TreeNode) -> Self {
+        let mut heatmap = Heatmap {
+            mode,
+            newest: None,
+            max_age: 0,
+            max_size: 0,
+            max_depth: 0,
+        };
+
+        let mut times = Vec::new();
+        heatmap.scan(root, 0, &mut times);
+        heatmap.newest = times.iter().max().copied();
+        if let Some(newest) = heatmap.newest {
+            heatmap.max_age = times
+                .iter()
+                .map(|t| age_secs(newest, *t))
+                .max()
+                .unwrap_or(0);
+        }
+        heatmap
+    }
+
+    fn scan(&mut self, node: &TreeNode, depth: usize, times: &mut Vec<SystemTime>) {
+        self.max_depth = self.max_depth.max(depth);
+        if let Some(ref meta) = node.metadata {
+            times.extend(meta.modified);
+            if !node.is_dir {
+                self.max_size = self.max_size.max(meta.size.unwrap_or(0));
+            }
+        }
+        for child in &node.children {
+            self.scan(child, depth + 1, times);
+        }
+    }
+
+    /// Returns the gradient color for `node` at `depth`, or `None` when the
+    /// node lacks the metadata the mode needs (directories in size mode).
+    pub fn color_for(&self, node: &TreeNode, depth: usize) -> Option<StyleColor> {
+        let heat = match self.mode {
+            ColorBy::Mtime => {
+                let modified = node.metadata.as_ref()?.modified?;
+                let age = age_secs(self.newest?, modified);
+                1.0 - log_ratio(age, self.max_age)
+            }
+            ColorBy::Size => {
+                if node.is_dir {
+                    return None;
+                }
+                let size = node.metadata.as_ref()?.size?;
+                log_ratio(size, self.max_size)
+            }
+            ColorBy::Depth => {
+                if self.max_depth == 0 {
+                    1.0
+                } else {
+                    1.0 - depth as f64 / self.max_depth as f64
+                }
+            }
+        };
+        Some(gradient(heat))
+    }
+}
+
+fn age_secs(newest: SystemTime, time: SystemTime) -> u64 {
+    newest
+        .duration_since(time)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Places `value` on a logarithmic 0..=1 scale up to `max`, so a handful of
+/// huge files or ancient entries don't flatten everything else.
+fn log_ratio(value: u64, max: u64) -> f64 {
+    if max == 0 {
+        return 0.0;
+    }
+    ((value as f64).ln_1p() / (max as f64).ln_1p()).clamp(0.0, 1.0)
+}
+
+/// Interpolates the gradient at `t` (0 = cold, 1 = hot).
+pub fn gradient(t: f64) -> StyleColor {
+    let t = t.clamp(0.0, 1.0) * (GRADIENT.len() - 1) as f64;
+    let i = (t.floor() as usize).min(GRADIENT.len() - 2);
+    let frac = t - i as f64;
+    let (a, b) = (GRADIENT[i], GRADIENT[i + 1]);
+    let lerp = |x: u8, y: u8| (x as f64 + (y as f64 - x as f64) * frac).round() as u8;
+    StyleColor::Rgb(lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::NodeMetadata;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn file(name: &str, size: u64, modified: SystemTime) -> TreeNode {
+        TreeNode {
+            name: name.to_string(),
+            path: PathBuf::from("/nonexistent").join(name),
+            metadata: Some(NodeMetadata {
+                size: Some(size),
+                modified: Some(modified),
+                ..NodeMetadata::default()
+            }),
+            ..TreeNode::default()
+        }
+    }
+
+    fn sample() -> TreeNode {
+        let now = SystemTime::now();
+        let mut root = file("root", 0, now);
+        root.children = vec![
+            file("fresh.rs", 10, now),
+            file("stale.rs", 10_000, now - Duration::from_secs(86_400 * 365)),
+        ];
+        root
+    }
+
+    #[test]
+    fn gradient_endpoints() {
+        assert_eq!(gradient(0.0), StyleColor::Rgb(88, 88, 98));
+        assert_eq!(gradient(1.0), StyleColor::Rgb(255, 90, 50));
+        assert_eq!(gradient(7.0), gradient(1.0));
+    }
+
+    #[test]
+    fn mtime_recent_is_hot() {
+        let tree = sample();
+        let heatmap = Heatmap::new(ColorBy::Mtime, &tree);
+        assert_eq!(heatmap.color_for(&tree.children[0], 1), Some(gradient(1.0)));
+        assert_eq!(heatmap.color_for(&tree.children[1], 1), Some(gradient(0.0)));
+    }
+
+    #[test]
+    fn size_largest_is_hot() {
+        let tree = sample();
+        let heatmap = Heatmap::new(ColorBy::Size, &tree);
+        assert_eq!(heatmap.color_for(&tree.children[1], 1), Some(gradient(1.0)));
+        assert_ne!(heatmap.color_for(&tree.children[0], 1), Some(gradient(1.0)));
+    }
+
+    #[test]
+    fn depth_root_is_hot() {
+        let tree = sample();
+        let heatmap = Heatmap::new(ColorBy::Depth, &tree);
+        assert_eq!(heatmap.color_for(&tree, 0), Some(gradient(1.0)));
+        assert_eq!(heatmap.color_for(&tree.children[0], 1), Some(gradient(0.0)));
+    }
+}
@@ -0,0 +1,352 @@
+//! Self-contained HTML export (`-F html`).
+//!
+//! The page is built from the same `ExportNode` data as the JSON export and
+//! styled by the active `Styler` and `IconMap`, so names get the colors they
+//! have in the terminal. Directories are `<details>` elements that collapse
+//! without JavaScript; a small inline script adds the filter box. Nothing is
+//! loaded from the network, so the file can be attached to tickets and
+//! opened offline.
+
+use std::fmt::Write;
+
+use crate::export::{ExportNode, NodeType, count_entries, escape_markup, to_export_node};
+use crate::render::{IconMap, format_size, icon_for_node};
+use crate::style::{StyleColor, Styler, TextStyle};
+use crate::tree::TreeNode;
+
+const STYLE: &str = r#"
+:root { color-scheme: COLOR_SCHEME; }
+body { margin: 0; background: BACKGROUND; color: FOREGROUND;
+  font: 14px/1.5 ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; }
+header { position: sticky; top: 0; display: flex; gap: 8px; align-items: center;
+  padding: 8px 16px; background: BACKGROUND; border-bottom: 1px solid LINES; }
+header input { flex: 1; max-width: 32em; font: inherit; padding: 2px 6px; }
+header button { font: inherit; }
+#count { opacity: 0.6; }
+main { padding: 8px 16px; }
+ul { list-style: none; margin: 0; padding: 0; }
+ul ul { margin-left: 0.6em; padding-left: 1em; border-left: 1px solid LINES; }
+summary { list-style: none; cursor: pointer; }
+summary::-webkit-details-marker { display: none; }
+.row { display: flex; gap: 2em; }
+.row::before { content: ""; display: inline-block; width: 1.2em; flex: none; }
+summary > .row::before { content: "▸"; color: LINES; }
+details[open] > summary > .row::before { content: "▾"; }
+.label { flex: 1; white-space: pre; }
+.meta { display: flex; gap: 1.5em; white-space: pre; }
+.meta span { min-width: 4em; text-align: right; }
+.meta .perm, .meta .owner { text-align: left; }
+.target { color: SYMLINK; }
+.note { opacity: 0.6; font-style: italic; }
+.match > .row .name, .match > details > summary .name { outline: 1px solid currentColor; }
+footer { padding: 8px 16px; opacity: 0.6; }
+"#;
+
+const SCRIPT: &str = r#"
+const input = document.getElementById("filter");
+const count = document.getElementById("count");
+const items = Array.from(document.querySelectorAll("main li")).reverse();
+const folders = document.querySelectorAll("main details");
+function visibleChild(li) {
+  return Array.from(li.querySelectorAll(":scope > details > ul > li")).some((c) => !c.hidden);
+}
+function apply() {
+  const query = input.value.trim().toLowerCase();
+  let matches = 0;
+  for (const li of items) {
+    const key = query.includes("/") ? li.dataset.path : li.dataset.name;
+    const match = query !== "" && key.toLowerCase().includes(query);
+    const child = query !== "" && visibleChild(li);
+    li.classList.toggle("match", match);
+    li.hidden = query !== "" && !match && !child && li.parentElement.id !== "root";
+    if (child) li.firstElementChild.open = true;
+    if (match) matches += 1;
+  }
+  count.textContent = query === "" ? "" : `${matches} matching`;
+}
+input.addEventListener("input", apply);
+document.getElementById("expand").onclick = () => folders.forEach((d) => (d.open = true));
+document.getElementById("collapse").onclick = () => folders.forEach((d) => (d.open = false));
+document.addEventListener("keydown", (e) => {
+  if (e.key === "/" && document.activeElement !== input) {
+    e.preventDefault();
+    input.focus();
+  }
+});
+"#;
+
+/// Renders `root` as a standalone HTML page.
+///
+/// Metadata columns are shown when the tree was loaded with `--long`; icons
+/// when `icon_map` is set.
+pub fn export_html(root: &TreeNode, styler: &Styler, icon_map: Option<&IconMap>) -> String {
+    let export = to_export_node(root);
+    let ui = styler.ui();
+    // Pick the page background that keeps the default file color readable
+    let dark = luminance(ui.file.to_rgb()) > 0.5;
+    let lines = ui.tree_lines.map_or(
+        if dark { "#555555" } else { "#bbbbbb" }.to_string(),
+        StyleColor::to_hex,
+    );
+    let style = STYLE
+        .replace("COLOR_SCHEME", if dark { "dark" } else { "light" })
+        .replace("BACKGROUND", if dark { "#1e1e1e" } else { "#fdfdfd" })
+        .replace("FOREGROUND", &ui.file.to_hex())
+        .replace("LINES", &lines)
+        .replace("SYMLINK", &ui.symlink.to_hex());
+
+    let mut body = String::new();
+    write_node(&export, root, styler, icon_map, &mut body);
+    let (dirs, files) = count_entries(&export);
+
+    format!(
+        "<!DOCTYPE html>\n\
+         <html lang=\"en\">\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <meta name=\"generator\" content=\"kree {version}\">\n\
+         <title>{title}</title>\n\
+         <style>{style}.meta {{ {meta} }}\n</style>\n\
+         </head>\n\
+         <body>\n\
+         <header>\
+         <input id=\"filter\" type=\"search\" placeholder=\"Filter (/ to focus)\" autofocus>\
+         <button id=\"expand\">Expand all</button>\
+         <button id=\"collapse\">Collapse all</button>\
+         <span id=\"count\"></span>\
+         </header>\n\
+         <main>\n<ul id=\"root\">\n{body}</ul>\n</main>\n\
+         <footer>{dirs} directories, {files} files</footer>\n\
+         <script>{SCRIPT}</script>\n\
+         </body>\n\
+         </html>\n",
+        version = env!("CARGO_PKG_VERSION"),
+        title = escape_markup(&export.name),
+        meta = inline_style(&ui.metadata),
+    )
+}
+
+/// Writes one `<li>`; `node` is the tree entry `export` was built from.
+fn write_node(
+    export: &ExportNode,
+    node: &TreeNode,
+    styler: &Styler,
+    icon_map: Option<&IconMap>,
+    out: &mut String,
+) {
+    let _ = write!(
+        out,
+        "<li data-name=\"{}\" data-path=\"{}\">",
+        escape_markup(&export.name),
+        escape_markup(&export.path)
+    );
+    let row = format!(
+        "<div class=\"row\"><span class=\"label\">{}</span>{}</div>",
+        label(export, node, styler, icon_map),
+        metadata(export)
+    );
+    if export.node_type == NodeType::Directory {
+        let _ = writeln!(out, "<details open><summary>{row}</summary><ul>");
+        for (child_export, child) in export.children.iter().zip(&node.children) {
+            write_node(child_export, child, styler, icon_map, out);
+        }
+        out.push_str("</ul></details>");
+    } else {
+        out.push_str(&row);
+    }
+    out.push_str("</li>\n");
+}
+
+/// Icon, colored name, symlink target and note of an entry.
+fn label(
+    export: &ExportNode,
+    node: &TreeNode,
+    styler: &Styler,
+    icon_map: Option<&IconMap>,
+) -> String {
+    let mut label = String::new();
+    if let Some(icon_map) = icon_map {
+        let icon = icon_for_node(
+            &node.path,
+            node.is_dir,
+            node.file_kind,
+            icon_map,
+            false,
+            styler.disk_lookups(),
+        );
+        let _ = write!(label, "{} ", escape_markup(icon));
+    }
+
+    let style = styler.style_for(&node.path, node.is_dir, node.is_symlink, node.file_kind);
+    let title = match &export.file_kind {
+        Some(kind) => format!(" title=\"{}\"", escape_markup(kind)),
+        None => String::new(),
+    };
+    let _ = write!(
+        label,
+        "<span class=\"name\" style=\"{}\"{title}>{}</span>",
+        inline_style(&style),
+        escape_markup(&export.name)
+    );
+
+    if let Some(target) = &export.symlink_target {
+        let _ = write!(
+            label,
+            " <span class=\"target\">-&gt; {}</span>",
+            escape_markup(target)
+        );
+    }
+    if let Some(note) = &export.note {
+        let _ = write!(
+            label,
+            "  <span class=\"note\"># {}</span>",
+            escape_markup(note)
+        );
+    }
+    label
+}
+
+/// Metadata columns, in the order of `--long`: permissions, owner, size and
+/// modification time. Empty when the export has no metadata.
+fn metadata(export: &ExportNode) -> String {
+    let mut columns = Vec::new();
+    if let Some(permissions) = &export.permissions {
+        columns.push(format!(
+            "<span class=\"perm\">{}</span>",
+            escape_markup(permissions)
+        ));
+    }
+    if let Some(owner) = &export.owner {
+        columns.push(format!(
+            "<span class=\"owner\">{}</span>",
+            escape_markup(owner)
+        ));
+    }
+    if let Some(size) = export.size {
+        columns.push(format!(
+            "<span class=\"size\" title=\"{size} bytes\">{}</span>",
+            format_size(size)
+        ));
+    }
+    if let Some(modified) = &export.modified {
+        columns.push(format!(
+            "<span class=\"date\">{}</span>",
+            escape_markup(modified)
+        ));
+    }
+
+    if columns.is_empty() {
+        String::new()
+    } else {
+        format!("<span class=\"meta\">{}</span>", columns.concat())
+    }
+}
+
+/// Converts a text style into CSS declarations.
+fn inline_style(style: &TextStyle) -> String {
+    let mut css_style = String::new();
+    if let Some(fg) = style.fg {
+        let _ = write!(css_style, "color:{};", fg.to_hex());
+    }
+    if let Some(bg) = style.bg {
+        let _ = write!(css_style, "background-color:{};", bg.to_hex());
+    }
+    if style.bold {
+        css_style.push_str("font-weight:bold;");
+    }
+    if style.dim {
+        css_style.push_str("opacity:0.6;");
+    }
+    if style.italic {
+        css_style.push_str("font-style:italic;");
+    }
+    if style.underline {
+        css_style.push_str("text-decoration:underline;");
+    }
+    css_style
+}
+
+/// Relative luminance in `0.0..=1.0`, without gamma correction.
+fn luminance((r, g, b): (u8, u8, u8)) -> f64 {
+    (0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64) / 255.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icons::IconSet;
+    use crate::render::{build_color_map, build_icon_map};
+    use crate::theme::UiTheme;
+    use crate::tree::NodeMetadata;
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+
+    fn styler() -> Styler {
+        Styler::new(
+            build_color_map(&HashMap::new()),
+            None,
+            Vec::new(),
+            UiTheme::default(),
+        )
+    }
+
+    fn sample_tree(metadata: Option<NodeMetadata>) -> TreeNode {
+        TreeNode {
+            name: "root".to_string(),
+            path: PathBuf::from("missing/root"),
+            is_dir: true,
+            children: vec![TreeNode {
+                name: "<main>.rs".to_string(),
+                path: PathBuf::from("missing/root/<main>.rs"),
+                metadata,
+                note: Some("entry & point".to_string()),
+                ..TreeNode::default()
+            }],
+            ..TreeNode::default()
+        }
+    }
+
+    #[test]
+    fn escapes_names_and_notes() {
+        let html = export_html(&sample_tree(None), &styler(), None);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("data-name=\"&lt;main&gt;.rs\""));
+        assert!(html.contains("# entry &amp; point"));
+        assert!(!html.contains("<main>.rs"));
+        assert!(html.contains("0 directories, 1 files"));
+    }
+
+    #[test]
+    fn uses_styler_colors_and_icons() {
+        let icons = build_icon_map(&HashMap::new(), IconSet::Ascii);
+        let html = export_html(&sample_tree(None), &styler(), Some(&icons));
+        let (r, g, b) = build_color_map(&HashMap::new())["rs"];
+        assert!(html.contains(&format!("color:#{r:02x}{g:02x}{b:02x};")));
+        // The default directory style is bold blue
+        assert!(html.contains("color:#0000ee;font-weight:bold;"));
+        let icon = icon_for_node(Path::new("x.rs"), false, None, &icons, false, true);
+        assert!(html.contains(&format!("{} <span class=\"name\"", escape_markup(icon))));
+    }
+
+    #[test]
+    fn metadata_columns_only_with_metadata() {
+        let html = export_html(&sample_tree(None), &styler(), None);
+        assert!(!html.contains("class=\"size\""));
+
+        let metadata = NodeMetadata {
+            size: Some(2048),
+            ..NodeMetadata::default()
+        };
+        let html = export_html(&sample_tree(Some(metadata)), &styler(), None);
+        assert!(html.contains("title=\"2048 bytes\">2.0K</span>"));
+    }
+
+    #[test]
+    fn has_no_external_assets() {
+        let html = export_html(&sample_tree(None), &styler(), None);
+        assert!(!html.contains("src="));
+        assert!(!html.contains("href="));
+        assert!(!html.contains("http"));
+    }
+}
@@ -0,0 +1,243 @@
+//! Built-in icon sets.
+//!
+//! Each set is a table of icon keys to glyphs, merged with the user's
+//! `[icons]` overrides by `render::build_icon_map`. Keys are:
+//!
+//! - `directory` / `directory_open`: closed and expanded folders
+//! - `dir:<name>`: folders with a specific (lowercase) name, e.g. `dir:src`
+//! - a lowercase file name, e.g. `cargo.toml` or `makefile`
+//! - a lowercase extension, e.g. `rs`
+//! - `executable` and `default` as fallbacks for files
+
+use clap::ValueEnum;
+
+/// Selectable icon set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum IconSet {
+    /// Nerd Font glyphs (requires a patched font).
+    #[default]
+    Nerd,
+    /// Emoji, supported by most modern terminals without extra fonts.
+    Emoji,
+    /// Plain ASCII markers.
+    Ascii,
+    /// Basic Unicode symbols available in nearly every font.
+    #[value(alias = "unicode-basic")]
+    Unicode,
+}
+
+impl IconSet {
+    /// Returns the default icon table for this set.
+    pub fn defaults(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            IconSet::Nerd => NERD_ICONS,
+            IconSet::Emoji => EMOJI_ICONS,
+            IconSet::Ascii => ASCII_ICONS,
+            IconSet::Unicode => UNICODE_ICONS,
+        }
+    }
+}
+
+const NERD_ICONS: &[(&str, &str)] = &[
+    // Special
+    ("directory", "\u{f114}"),      //
+    ("directory_open", "\u{f115}"), //
+    ("executable", "\u{f489}"),     //
+    ("default", "\u{f15b}"),        //
+    // Folders
+    ("dir:.git", "\u{e5fb}"),         //
+    ("dir:.github", "\u{e5fd}"),      //
+    ("dir:.config", "\u{e5fc}"),      //
+    ("dir:.vscode", "\u{e70c}"),      //
+    ("dir:node_modules", "\u{e5fa}"), //
+    ("dir:src", "\u{f121}"),          //
+    ("dir:tests", "\u{f0c3}"),        //
+    ("dir:test", "\u{f0c3}"),         //
+    ("dir:docs", "\u{f02d}"),         //
+    ("dir:doc", "\u{f02d}"),          //
+    // File names
+    ("cargo.toml", "\u{e7a8}"),        //
+    ("cargo.lock", "\u{e7a8}"),        //
+    ("package.json", "\u{e71e}"),      //
+    ("package-lock.json", "\u{e71e}"), //
+    ("go.mod", "\u{e626}"),            //
+    ("go.sum", "\u{e626}"),            //
+    ("makefile", "\u{f0ad}"),          //
+    ("justfile", "\u{f0ad}"),          //
+    ("cmakelists.txt", "\u{f0ad}"),    //
+    ("dockerfile", "\u{e7b0}"),        //
+    (".gitignore", "\u{e702}"),        //
+    (".gitattributes", "\u{e702}"),    //
+    ("readme.md", "\u{f05a}"),         //
+    ("license", "\u{f0e3}"),           //
+    // Languages
+    ("rs", "\u{e7a8}"),    //
+    ("py", "\u{e73c}"),    //
+    ("js", "\u{e74e}"),    //
+    ("ts", "\u{e628}"),    //
+    ("jsx", "\u{e7ba}"),   //
+    ("tsx", "\u{e7ba}"),   //
+    ("go", "\u{e626}"),    //
+    ("rb", "\u{e739}"),    //
+    ("java", "\u{e738}"),  //
+    ("c", "\u{e61e}"),     //
+    ("cpp", "\u{e61d}"),   //
+    ("h", "\u{e61e}"),     //
+    ("hpp", "\u{e61d}"),   //
+    ("lua", "\u{e620}"),   //
+    ("php", "\u{e73d}"),   //
+    ("swift", "\u{e755}"), //
+    ("kt", "\u{e634}"),    //
+    ("dart", "\u{e798}"),  //
+    ("zig", "\u{e6a9}"),   //
+    ("ex", "\u{e62d}"),    //
+    ("hs", "\u{e61f}"),    //
+    ("sh", "\u{f489}"),    //
+    ("bash", "\u{f489}"),  //
+    ("zsh", "\u{f489}"),   //
+    ("cs", "\u{f81a}"),    //
+    ("r", "\u{f25d}"),     //
+    // Web
+    ("html", "\u{e736}"), //
+    ("css", "\u{e749}"),  //
+    ("scss", "\u{e749}"), //
+    ("vue", "\u{e6a0}"),  //
+    // Data / Config
+    ("json", "\u{e60b}"), //
+    ("toml", "\u{e60b}"), //
+    ("yaml", "\u{e60b}"), //
+    ("yml", "\u{e60b}"),  //
+    ("xml", "\u{e619}"),  //
+    ("csv", "\u{f1c3}"),  //
+    // Docs
+    ("md", "\u{e73e}"),  //
+    ("txt", "\u{f15c}"), //
+    ("rst", "\u{f15c}"), //
+    ("pdf", "\u{f1c1}"), //
+    // Images
+    ("png", "\u{f1c5}"),  //
+    ("jpg", "\u{f1c5}"),  //
+    ("jpeg", "\u{f1c5}"), //
+    ("gif", "\u{f1c5}"),  //
+    ("svg", "\u{f1c5}"),  //
+    ("ico", "\u{f1c5}"),  //
+    ("bmp", "\u{f1c5}"),  //
+    ("webp", "\u{f1c5}"), //
+    // Archives
+    ("zip", "\u{f1c6}"), //
+    ("tar", "\u{f1c6}"), //
+    ("gz", "\u{f1c6}"),  //
+    ("bz2", "\u{f1c6}"), //
+    ("xz", "\u{f1c6}"),  //
+    ("rar", "\u{f1c6}"), //
+    ("7z", "\u{f1c6}"),  //
+    // Other
+    ("lock", "\u{f023}"),      //
+    ("gitignore", "\u{e702}"), //
+];
+
+const EMOJI_ICONS: &[(&str, &str)] = &[
+    // Special
+    ("directory", "📁"),
+    ("directory_open", "📂"),
+    ("executable", "⚡"),
+    ("default", "📄"),
+    // Folders
+    ("dir:.git", "🌱"),
+    ("dir:.github", "🐙"),
+    ("dir:node_modules", "📦"),
+    ("dir:src", "🧩"),
+    ("dir:tests", "🧪"),
+    ("dir:test", "🧪"),
+    ("dir:docs", "📚"),
+    ("dir:doc", "📚"),
+    // File names
+    ("cargo.toml", "🦀"),
+    ("cargo.lock", "🔒"),
+    ("package.json", "📦"),
+    ("package-lock.json", "🔒"),
+    ("makefile", "🔨"),
+    ("justfile", "🔨"),
+    ("dockerfile", "🐳"),
+    (".gitignore", "🙈"),
+    ("readme.md", "📖"),
+    ("license", "📜"),
+    // Languages
+    ("rs", "🦀"),
+    ("py", "🐍"),
+    ("go", "🐹"),
+    ("rb", "💎"),
+    ("java", "☕"),
+    ("js", "📜"),
+    ("ts", "📜"),
+    ("sh", "🐚"),
+    ("bash", "🐚"),
+    ("zsh", "🐚"),
+    // Web
+    ("html", "🌐"),
+    ("css", "🎨"),
+    ("scss", "🎨"),
+    // Data / Config
+    ("json", "🔧"),
+    ("toml", "🔧"),
+    ("yaml", "🔧"),
+    ("yml", "🔧"),
+    ("csv", "📊"),
+    // Docs
+    ("md", "📝"),
+    ("txt", "📝"),
+    ("pdf", "📕"),
+    // Images
+    ("png", "📷"),
+    ("jpg", "📷"),
+    ("jpeg", "📷"),
+    ("gif", "📷"),
+    ("svg", "📷"),
+    ("webp", "📷"),
+    // Archives
+    ("zip", "📦"),
+    ("tar", "📦"),
+    ("gz", "📦"),
+    ("xz", "📦"),
+    ("7z", "📦"),
+    // Other
+    ("lock", "🔒"),
+];
+
+const ASCII_ICONS: &[(&str, &str)] = &[
+    ("directory", "+"),
+    ("directory_open", "-"),
+    ("executable", "*"),
+    ("default", "."),
+];
+
+const UNICODE_ICONS: &[(&str, &str)] = &[
+    ("directory", "▸"),
+    ("directory_open", "▾"),
+    ("executable", "★"),
+    ("default", "•"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_set_has_fallbacks() {
+        for set in IconSet::value_variants() {
+            let keys: Vec<&str> = set.defaults().iter().map(|&(k, _)| k).collect();
+            for required in ["directory", "directory_open", "executable", "default"] {
+                assert!(keys.contains(&required), "{set:?} lacks {required}");
+            }
+        }
+    }
+
+    #[test]
+    fn keys_are_lowercase() {
+        for set in IconSet::value_variants() {
+            for &(key, _) in set.defaults() {
+                assert_eq!(key, key.to_lowercase());
+            }
+        }
+    }
+}
@@ -0,0 +1,512 @@
+//! Lines-of-code counts (`--loc`).
+//!
+//! Files of a known language are split into code, comment and blank lines.
+//! Strings are not parsed: a line counts as comment when it holds nothing
+//! but comments, and as code when anything else is on it. Binary files and
+//! files over 10 MB are skipped. Directories carry the totals of the files
+//! below them, including those past the depth limit.
+
+use std::collections::HashMap;
+use std::fs;
+use std::ops::AddAssign;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::filetype::{self, FileKind};
+use crate::search::MAX_FILE_SIZE;
+use crate::tree::TreeNode;
+
+/// Code, comment and blank lines of a file or directory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineCounts {
+    pub code: u64,
+    pub comment: u64,
+    pub blank: u64,
+}
+
+impl AddAssign for LineCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.code += other.code;
+        self.comment += other.comment;
+        self.blank += other.blank;
+    }
+}
+
+/// Comment delimiters of a language.
+struct Syntax {
+    line: &'static [&'static str],
+    /// Opening and closing delimiters of block comments.
+    block: &'static [(&'static str, &'static str)],
+}
+
+const C_LIKE: Syntax = Syntax {
+    line: &["//"],
+    block: &[("/*", "*/")],
+};
+const HASH: Syntax = Syntax {
+    line: &["#"],
+    block: &[],
+};
+const MARKUP: Syntax = Syntax {
+    line: &[],
+    block: &[("<!--", "-->")],
+};
+
+/// Languages by file kind name (see `filetype`).
+const SYNTAXES: &[(&str, Syntax)] = &[
+    ("rust", C_LIKE),
+    ("go", C_LIKE),
+    ("c", C_LIKE),
+    ("cpp", C_LIKE),
+    ("java", C_LIKE),
+    ("javascript", C_LIKE),
+    ("typescript", C_LIKE),
+    (
+        "php",
+        Syntax {
+            line: &["//", "#"],
+            block: &[("/*", "*/")],
+        },
+    ),
+    (
+        "css",
+        Syntax {
+            line: &[],
+            block: &[("/*", "*/")],
+        },
+    ),
+    (
+        "python",
+        Syntax {
+            line: &["#"],
+            block: &[("\"\"\"", "\"\"\""), ("'''", "'''")],
+        },
+    ),
+    (
+        "ruby",
+        Syntax {
+            line: &["#"],
+            block: &[("=begin", "=end")],
+        },
+    ),
+    (
+        "perl",
+        Syntax {
+            line: &["#"],
+            block: &[("=pod", "=cut")],
+        },
+    ),
+    (
+        "lua",
+        Syntax {
+            line: &["--"],
+            block: &[("--[[", "]]")],
+        },
+    ),
+    ("shell", HASH),
+    ("makefile", HASH),
+    ("dockerfile", HASH),
+    ("cmake", HASH),
+    ("toml", HASH),
+    ("yaml", HASH),
+    (
+        "json",
+        Syntax {
+            line: &[],
+            block: &[],
+        },
+    ),
+    ("html", MARKUP),
+    ("xml", MARKUP),
+    ("markdown", MARKUP),
+];
+
+/// Returns the language of a file: its detected kind, or the kind of its
+/// extension without `--detect`.
+pub fn language(path: &Path, kind: Option<FileKind>) -> Option<FileKind> {
+    kind.or_else(|| filetype::from_extension(path))
+        .filter(|kind| syntax(kind.name).is_some())
+}
+
+/// Returns the line comment markers of `kind`'s language (empty if it has
+/// none or is not a known language).
+pub fn line_comments(kind: FileKind) -> &'static [&'static str] {
+    syntax(kind.name).map_or(&[], |syntax| syntax.line)
+}
+
+fn syntax(name: &str) -> Option<&'static Syntax> {
+    SYNTAXES
+        .iter()
+        .find(|(language, _)| *language == name)
+        .map(|(_, syntax)| syntax)
+}
+
+/// Counts the lines of the file at `path`, or returns `None` if its
+/// language is unknown or it is binary, too large, unreadable or not a
+/// regular file (reading a FIFO would block).
+pub fn count_file(path: &Path, kind: Option<FileKind>) -> Option<LineCounts> {
+    let syntax = syntax(language(path, kind)?.name)?;
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_FILE_SIZE {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    if filetype::is_binary(&bytes) {
+        return None;
+    }
+    Some(count_lines(&String::from_utf8_lossy(&bytes), syntax))
+}
+
+fn count_lines(text: &str, syntax: &Syntax) -> LineCounts {
+    let mut counts = LineCounts::default();
+    // Closing delimiter of the block comment the current line starts in
+    let mut block_end: Option<&str> = None;
+    for line in text.lines() {
+        let mut rest = line.trim();
+        if rest.is_empty() {
+            counts.blank += 1;
+            continue;
+        }
+        let mut has_code = false;
+        let mut has_comment = block_end.is_some();
+        loop {
+            if let Some(end) = block_end {
+                match rest.find(end) {
+                    Some(i) => {
+                        rest = &rest[i + end.len()..];
+                        block_end = None;
+                    }
+                    None => break,
+                }
+                continue;
+            }
+            let line_start = syntax.line.iter().filter_map(|d| rest.find(d)).min();
+            // At the same position a block opener wins (`--[[` over `--`)
+            let block_start = syntax
+                .block
+                .iter()
+                .filter_map(|&(start, end)| rest.find(start).map(|i| (i, start, end)))
+                .min_by_key(|&(i, start, _)| (i, usize::MAX - start.len()));
+            match (line_start, block_start) {
+                (_, Some((i, start, end))) if line_start.is_none_or(|l| i <= l) => {
+                    has_code |= !rest[..i].trim().is_empty();
+                    has_comment = true;
+                    rest = &rest[i + start.len()..];
+                    block_end = Some(end);
+                }
+                (Some(i), _) => {
+                    has_code |= !rest[..i].trim().is_empty();
+                    has_comment = true;
+                    break;
+                }
+                (None, _) => {
+                    has_code |= !rest.trim().is_empty();
+                    break;
+                }
+            }
+        }
+        if has_code || !has_comment {
+            counts.code += 1;
+        } else {
+            counts.comment += 1;
+        }
+    }
+    counts
+}
+
+/// Sets the counts of every directory to the sum of the files below it and
+/// returns the counts of `node`. Directories without counted files get
+/// `None`; directories whose children were not loaded keep the totals
+/// `load_tree` gave them at the depth limit.
+pub fn sum_tree(node: &mut TreeNode) -> Option<LineCounts> {
+    if !node.is_dir || node.children.is_empty() {
+        return node.loc;
+    }
+    let mut total = None;
+    for child in &mut node.children {
+        if let Some(counts) = sum_tree(child) {
+            *total.get_or_insert_with(LineCounts::default) += counts;
+        }
+    }
+    node.loc = total;
+    total
+}
+
+/// Formats the code lines of a file or directory for the tree column.
+pub fn format_loc(counts: &LineCounts) -> String {
+    format!("{} loc", counts.code)
+}
+
+/// Line counts of the files of one language.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LanguageTotals {
+    name: &'static str,
+    files: usize,
+    counts: LineCounts,
+}
+
+/// Line counts by language, keyed by file kind name.
+pub type Languages = HashMap<&'static str, LanguageTotals>;
+
+/// Adds the counts of the file at `path` to the totals of its language.
+pub fn add_file(
+    languages: &mut Languages,
+    path: &Path,
+    kind: Option<FileKind>,
+    counts: LineCounts,
+) {
+    let Some(kind) = language(path, kind) else {
+        return;
+    };
+    add_totals(
+        languages,
+        LanguageTotals {
+            name: kind.name,
+            files: 1,
+            counts,
+        },
+    );
+}
+
+/// Adds every language of `other` to `languages`.
+pub fn merge_languages(languages: &mut Languages, other: &Languages) {
+    for totals in other.values() {
+        add_totals(languages, *totals);
+    }
+}
+
+fn add_totals(languages: &mut Languages, totals: LanguageTotals) {
+    let entry = languages.entry(totals.name).or_insert(LanguageTotals {
+        name: totals.name,
+        files: 0,
+        counts: LineCounts::default(),
+    });
+    entry.files += totals.files;
+    entry.counts += totals.counts;
+}
+
+/// Returns the sum of all languages, or `None` when no file was counted.
+pub fn total(languages: &Languages) -> Option<LineCounts> {
+    languages
+        .values()
+        .map(|totals| totals.counts)
+        .reduce(|mut total, counts| {
+            total += counts;
+            total
+        })
+}
+
+/// Collects the languages of the counted files below `node`. Directories
+/// whose children were not loaded contribute the totals `load_tree` gave
+/// them at the depth limit, the same data as their `loc`.
+fn collect_languages(node: &TreeNode, languages: &mut Languages) {
+    if !node.is_dir {
+        if let Some(counts) = node.loc {
+            add_file(languages, &node.path, node.file_kind, counts);
+        }
+    } else if node.children.is_empty() {
+        merge_languages(languages, &node.loc_languages);
+    } else {
+        for child in &node.children {
+            collect_languages(child, languages);
+        }
+    }
+}
+
+/// Formats the per-language table printed below the tree, largest
+/// language first, or an empty string when no file was counted. The
+/// renderer styles the header row.
+pub fn format_language_summary(root: &TreeNode) -> String {
+    let mut totals = Languages::new();
+    collect_languages(root, &mut totals);
+    if totals.is_empty() {
+        return String::new();
+    }
+    let mut languages: Vec<LanguageTotals> = totals.into_values().collect();
+    languages.sort_by(|a, b| {
+        b.counts
+            .code
+            .cmp(&a.counts.code)
+            .then_with(|| a.name.cmp(b.name))
+    });
+    let mut total = LanguageTotals {
+        name: "Total",
+        files: 0,
+        counts: LineCounts::default(),
+    };
+    for language in &languages {
+        total.files += language.files;
+        total.counts += language.counts;
+    }
+
+    let width = languages
+        .iter()
+        .map(|l| l.name.len())
+        .chain([8])
+        .max()
+        .unwrap_or(8);
+    let row = |name: &str, files: &str, code: &str, comment: &str, blank: &str| {
+        format!("{name:<width$}  {files:>6}  {code:>8}  {comment:>8}  {blank:>8}\n")
+    };
+    let mut output = row("Language", "Files", "Code", "Comment", "Blank");
+    for language in languages.iter().chain([&total]) {
+        output.push_str(&row(
+            language.name,
+            &language.files.to_string(),
+            &language.counts.code.to_string(),
+            &language.counts.comment.to_string(),
+            &language.counts.blank.to_string(),
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn count(text: &str, language: &str) -> LineCounts {
+        count_lines(text, syntax(language).unwrap())
+    }
+
+    #[test]
+    fn counts_c_like_comments() {
+        let source = "\
+//! Crate docs
+
+/* a block
+   comment */
+fn main() { // trailing
+    let x = 1; /* inline */
+    /* open */ let y = 2;
+
+}
+";
+        assert_eq!(
+            count(source, "rust"),
+            LineCounts {
+                code: 4,
+                comment: 3,
+                blank: 2
+            }
+        );
+    }
+
+    #[test]
+    fn block_opener_wins_over_line_comment() {
+        let source = "--[[ long\ncomment ]]\n-- short\nprint(1)\n";
+        assert_eq!(
+            count(source, "lua"),
+            LineCounts {
+                code: 1,
+                comment: 3,
+                blank: 0
+            }
+        );
+        let python = "\"\"\"Module docs.\"\"\"\nimport os  # why\n# note\n";
+        assert_eq!(
+            count(python, "python"),
+            LineCounts {
+                code: 1,
+                comment: 2,
+                blank: 0
+            }
+        );
+    }
+
+    #[test]
+    fn skips_unknown_and_binary_files() {
+        let dir = tempdir().unwrap();
+        let text = dir.path().join("notes.txt");
+        let binary = dir.path().join("blob.rs");
+        let source = dir.path().join("lib.rs");
+        fs::write(&text, "hello\n").unwrap();
+        fs::write(&binary, b"fn\0main").unwrap();
+        fs::write(&source, "fn f() {}\n\n").unwrap();
+        assert_eq!(count_file(&text, None), None);
+        assert_eq!(count_file(&binary, None), None);
+        assert_eq!(
+            count_file(&source, None),
+            Some(LineCounts {
+                code: 1,
+                comment: 0,
+                blank: 1
+            })
+        );
+    }
+
+    #[test]
+    fn totals_include_files_past_the_depth_limit() {
+        use crate::ignore::IgnoreFilter;
+        use crate::tree::{SortMode, TreeOptions, load_tree};
+
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/deep/deeper")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "fn a() {}\n").unwrap();
+        fs::write(dir.path().join("src/deep/deeper/b.py"), "b = 1\n").unwrap();
+        // Symlinks below the depth limit are not followed
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(".", dir.path().join("src/deep/self")).unwrap();
+        let filter = IgnoreFilter::with_gitignore(false, &[], false, dir.path());
+        let opts = TreeOptions {
+            loc: true,
+            ..TreeOptions::default()
+        };
+        let mut root = load_tree(&dir.path().into(), 2, 0, &filter, SortMode::Kind, &opts);
+        let total = sum_tree(&mut root).unwrap();
+        assert_eq!(total.code, 2);
+        let deep = &root.children[0].children[0];
+        assert!(deep.children.is_empty());
+        assert_eq!(deep.loc.unwrap().code, 1);
+
+        // The language summary counts the same files as the totals
+        let mut languages = Languages::new();
+        collect_languages(&root, &mut languages);
+        assert_eq!(languages["python"].files, 1);
+        assert_eq!(languages["rust"].files, 1);
+        assert_eq!(super::total(&languages), Some(total));
+    }
+
+    #[test]
+    fn sums_directories_and_languages() {
+        let file = |name: &str, code| TreeNode {
+            name: name.to_string(),
+            path: name.into(),
+            loc: Some(LineCounts {
+                code,
+                comment: 1,
+                blank: 0,
+            }),
+            ..TreeNode::default()
+        };
+        let mut root = TreeNode {
+            is_dir: true,
+            children: vec![
+                TreeNode {
+                    is_dir: true,
+                    children: vec![file("a.rs", 10), file("b.py", 3)],
+                    ..TreeNode::default()
+                },
+                TreeNode {
+                    is_dir: true,
+                    ..TreeNode::default()
+                },
+                file("c.rs", 5),
+            ],
+            ..TreeNode::default()
+        };
+        let total = sum_tree(&mut root).unwrap();
+        assert_eq!((total.code, total.comment), (18, 3));
+        assert_eq!(root.children[0].loc.unwrap().code, 13);
+        assert_eq!(root.children[1].loc, None);
+
+        let summary = format_language_summary(&root);
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines[0], "Language   Files      Code   Comment     Blank");
+        assert_eq!(lines[1], "rust           2        15         2         0");
+        assert!(lines[2].starts_with("python         1         3"));
+        assert!(lines[3].starts_with("Total          3        18"));
+    }
+}
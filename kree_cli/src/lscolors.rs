@@ -0,0 +1,397 @@
+//! LS_COLORS and dircolors support.
+//!
+//! Parses the `LS_COLORS` environment variable (as produced by `dircolors`)
+//! or a `dircolors` database file, so Kree can color entries the same way
+//! `ls` and `eza` do.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::LsColorsConfig;
+use crate::render::is_executable;
+use crate::style::{StyleColor, TextStyle};
+
+/// Parsed LS_COLORS rules.
+#[derive(Debug, Default)]
+pub struct LsColors {
+    /// Styles for file-type keys (`di`, `ln`, `ex`, ...).
+    types: HashMap<String, LsStyle>,
+    /// Styles for `*suffix` entries, in definition order.
+    suffixes: Vec<(String, TextStyle)>,
+}
+
+/// Value of an LS_COLORS entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LsStyle {
+    Style(TextStyle),
+    /// `ln=target`: color symlinks like the file they point to.
+    Target,
+}
+
+/// Mapping of dircolors database keywords to LS_COLORS keys.
+const DIRCOLORS_KEYWORDS: &[(&str, &str)] = &[
+    ("NORMAL", "no"),
+    ("NORM", "no"),
+    ("FILE", "fi"),
+    ("RESET", "rs"),
+    ("DIR", "di"),
+    ("LNK", "ln"),
+    ("LINK", "ln"),
+    ("SYMLINK", "ln"),
+    ("ORPHAN", "or"),
+    ("MISSING", "mi"),
+    ("FIFO", "pi"),
+    ("PIPE", "pi"),
+    ("SOCK", "so"),
+    ("DOOR", "do"),
+    ("BLK", "bd"),
+    ("BLOCK", "bd"),
+    ("CHR", "cd"),
+    ("CHAR", "cd"),
+    ("EXEC", "ex"),
+    ("SETUID", "su"),
+    ("SETGID", "sg"),
+    ("CAPABILITY", "ca"),
+    ("STICKY_OTHER_WRITABLE", "tw"),
+    ("OTHER_WRITABLE", "ow"),
+    ("STICKY", "st"),
+    ("MULTIHARDLINK", "mh"),
+];
+
+impl LsColors {
+    /// Loads LS_COLORS rules according to the `[ls_colors]` configuration.
+    ///
+    /// Returns `None` when disabled. A configured `dircolors` file takes
+    /// precedence over the `LS_COLORS` environment variable.
+    pub fn load(config: &LsColorsConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+
+        if let Some(ref file) = config.dircolors {
+            let path = expand_home(file);
+            return match fs::read_to_string(&path) {
+                Ok(contents) => Some(Self::from_dircolors(&contents)),
+                Err(e) => {
+                    eprintln!(
+                        "Warning: cannot read dircolors file '{}': {e}",
+                        path.display()
+                    );
+                    None
+                }
+            };
+        }
+
+        std::env::var("LS_COLORS")
+            .ok()
+            .map(|spec| Self::parse(&spec))
+    }
+
+    /// Parses an `LS_COLORS` string such as `di=01;34:ln=01;36:*.tar=01;31`.
+    pub fn parse(spec: &str) -> Self {
+        let mut colors = LsColors::default();
+        for entry in spec.split(':') {
+            if let Some((key, value)) = entry.split_once('=') {
+                colors.insert(key, value);
+            }
+        }
+        colors
+    }
+
+    /// Parses a `dircolors` database (the format of `dircolors -p`).
+    ///
+    /// `TERM`, `COLORTERM` and option lines are ignored, so every entry applies.
+    pub fn from_dircolors(contents: &str) -> Self {
+        let mut colors = LsColors::default();
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut parts = line.split_whitespace();
+            let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+
+            if key.starts_with('.') {
+                colors.insert(&format!("*{key}"), value);
+            } else if key.starts_with('*') {
+                colors.insert(key, value);
+            } else if let Some(&(_, short)) = DIRCOLORS_KEYWORDS
+                .iter()
+                .find(|(kw, _)| kw.eq_ignore_ascii_case(key))
+            {
+                colors.insert(short, value);
+            }
+        }
+        colors
+    }
+
+    fn insert(&mut self, key: &str, value: &str) {
+        if let Some(suffix) = key.strip_prefix('*') {
+            self.suffixes
+                .push((suffix.to_lowercase(), parse_sgr(value)));
+        } else if key == "ln" && value == "target" {
+            self.types.insert(key.to_string(), LsStyle::Target);
+        } else {
+            self.types
+                .insert(key.to_string(), LsStyle::Style(parse_sgr(value)));
+        }
+    }
+
+    fn type_style(&self, key: &str) -> Option<TextStyle> {
+        match self.types.get(key) {
+            Some(LsStyle::Style(style)) if *style != TextStyle::default() => Some(*style),
+            _ => None,
+        }
+    }
+
+    fn suffix_style(&self, path: &Path) -> Option<TextStyle> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        // Later definitions override earlier ones, as in GNU ls
+        self.suffixes
+            .iter()
+            .rev()
+            .find(|(suffix, _)| name.ends_with(suffix.as_str()))
+            .map(|(_, style)| *style)
+    }
+
+    /// Returns the style for an entry from its type and name alone, without
+    /// looking it up on the disk. Symlinks get `ln`, directories `di` and
+    /// other entries their suffix style or `fi`.
+    pub fn style_for_name(&self, path: &Path, is_dir: bool, is_symlink: bool) -> Option<TextStyle> {
+        if is_symlink {
+            return self.type_style("ln");
+        }
+        if is_dir {
+            return self.type_style("di");
+        }
+        self.suffix_style(path).or_else(|| self.type_style("fi"))
+    }
+
+    /// Returns the style for the entry at `path`, or `None` if no rule applies.
+    ///
+    /// File-type keys take precedence over suffix entries, except for regular
+    /// files, which are matched by suffix before falling back to `fi`.
+    ///
+    /// `is_dir` is used for entries that don't exist on disk (e.g. trees built
+    /// from a path list).
+    pub fn style_for(&self, path: &Path, is_dir: bool, is_symlink: bool) -> Option<TextStyle> {
+        if is_symlink {
+            if fs::metadata(path).is_err() {
+                return self.type_style("or").or_else(|| self.type_style("ln"));
+            }
+            if self.types.get("ln") != Some(&LsStyle::Target) {
+                return self.type_style("ln");
+            }
+        }
+
+        let Ok(meta) = fs::metadata(path) else {
+            return self.style_for_name(path, is_dir, false);
+        };
+
+        if meta.is_dir() {
+            return self.dir_style(&meta).or_else(|| self.type_style("di"));
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+            let file_type = meta.file_type();
+            let special = if file_type.is_fifo() {
+                Some("pi")
+            } else if file_type.is_socket() {
+                Some("so")
+            } else if file_type.is_block_device() {
+                Some("bd")
+            } else if file_type.is_char_device() {
+                Some("cd")
+            } else {
+                None
+            };
+            if let Some(key) = special {
+                return self.type_style(key);
+            }
+
+            let mode = meta.permissions().mode();
+            if mode & 0o4000 != 0
+                && let Some(style) = self.type_style("su")
+            {
+                return Some(style);
+            }
+            if mode & 0o2000 != 0
+                && let Some(style) = self.type_style("sg")
+            {
+                return Some(style);
+            }
+        }
+
+        if is_executable(path)
+            && let Some(style) = self.type_style("ex")
+        {
+            return Some(style);
+        }
+
+        self.suffix_style(path).or_else(|| self.type_style("fi"))
+    }
+
+    /// Returns the sticky / other-writable directory style, if any applies.
+    #[cfg(unix)]
+    fn dir_style(&self, meta: &fs::Metadata) -> Option<TextStyle> {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = meta.permissions().mode();
+        let sticky = mode & 0o1000 != 0;
+        let other_writable = mode & 0o002 != 0;
+        match (sticky, other_writable) {
+            (true, true) => self.type_style("tw"),
+            (false, true) => self.type_style("ow"),
+            (true, false) => self.type_style("st"),
+            (false, false) => None,
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn dir_style(&self, _meta: &fs::Metadata) -> Option<TextStyle> {
+        None
+    }
+}
+
+/// Parses an SGR parameter string such as `01;38;5;208` into a `TextStyle`.
+///
+/// Unsupported attributes (blink, reverse, ...) are ignored.
+fn parse_sgr(value: &str) -> TextStyle {
+    let codes: Vec<u8> = value
+        .split(';')
+        .filter_map(|c| {
+            if c.is_empty() {
+                Some(0)
+            } else {
+                c.parse().ok()
+            }
+        })
+        .collect();
+
+    let mut style = TextStyle::default();
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 => style = TextStyle::default(),
+            1 => style.bold = true,
+            2 => style.dim = true,
+            3 => style.italic = true,
+            4 => style.underline = true,
+            c @ 30..=37 => style.fg = Some(StyleColor::Indexed(c - 30)),
+            c @ 40..=47 => style.bg = Some(StyleColor::Indexed(c - 40)),
+            c @ 90..=97 => style.fg = Some(StyleColor::Indexed(c - 90 + 8)),
+            c @ 100..=107 => style.bg = Some(StyleColor::Indexed(c - 100 + 8)),
+            c @ (38 | 48) => {
+                let color = match codes.get(i + 1) {
+                    Some(5) => {
+                        let color = codes.get(i + 2).map(|&n| StyleColor::Indexed(n));
+                        i += 2;
+                        color
+                    }
+                    Some(2) => {
+                        let color = match (codes.get(i + 2), codes.get(i + 3), codes.get(i + 4)) {
+                            (Some(&r), Some(&g), Some(&b)) => Some(StyleColor::Rgb(r, g, b)),
+                            _ => None,
+                        };
+                        i += 4;
+                        color
+                    }
+                    _ => None,
+                };
+                if c == 38 {
+                    style.fg = color;
+                } else {
+                    style.bg = color;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    style
+}
+
+/// Expands a leading `~/` to the user's home directory.
+fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(rest);
+    }
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn parse_sgr_bold_color() {
+        let style = parse_sgr("01;34");
+        assert!(style.bold);
+        assert_eq!(style.fg, Some(StyleColor::Indexed(4)));
+    }
+
+    #[test]
+    fn parse_sgr_extended_colors() {
+        let style = parse_sgr("38;5;208;48;2;10;20;30;04");
+        assert_eq!(style.fg, Some(StyleColor::Indexed(208)));
+        assert_eq!(style.bg, Some(StyleColor::Rgb(10, 20, 30)));
+        assert!(style.underline);
+    }
+
+    #[test]
+    fn suffix_and_directory_rules() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("archive.TAR"), "").unwrap();
+        let colors = LsColors::parse("di=01;34:*.tar=01;31");
+
+        let dir_style = colors.style_for(dir.path(), true, false).unwrap();
+        assert_eq!(dir_style.fg, Some(StyleColor::Indexed(4)));
+
+        let tar_style = colors
+            .style_for(&dir.path().join("archive.TAR"), false, false)
+            .unwrap();
+        assert_eq!(tar_style.fg, Some(StyleColor::Indexed(1)));
+    }
+
+    #[test]
+    fn unmatched_file_falls_through() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("main.rs"), "").unwrap();
+        let colors = LsColors::parse("di=01;34");
+        assert!(
+            colors
+                .style_for(&dir.path().join("main.rs"), false, false)
+                .is_none()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn broken_symlink_uses_orphan() {
+        let dir = tempdir().unwrap();
+        let link = dir.path().join("dangling");
+        std::os::unix::fs::symlink(dir.path().join("nowhere"), &link).unwrap();
+        let colors = LsColors::parse("ln=01;36:or=40;31;01");
+        let style = colors.style_for(&link, false, true).unwrap();
+        assert_eq!(style.fg, Some(StyleColor::Indexed(1)));
+        assert_eq!(style.bg, Some(StyleColor::Indexed(0)));
+    }
+
+    #[test]
+    fn dircolors_database() {
+        let db = "# comment\nTERM xterm*\nDIR 01;34 # dirs\n.md 00;33\n*Makefile 01;32\n";
+        let colors = LsColors::from_dircolors(db);
+        assert_eq!(
+            colors.types.get("di"),
+            Some(&LsStyle::Style(
+                TextStyle::fg(StyleColor::Indexed(4)).bold()
+            ))
+        );
+        assert_eq!(colors.suffixes.len(), 2);
+        assert_eq!(colors.suffixes[0].0, ".md");
+    }
+}
@@ -9,38 +9,97 @@
 //! - Fuzzy search for finding files and directories quickly
 //! - Interactive TUI mode for navigating the file system
 //! - Support for ignoring files (via `.kreeignore` and `.gitignore` conventions)
-//! - Syntax highlighting and file icons (via Nerd Fonts)
+//! - Syntax highlighting and file icons (Nerd Fonts, emoji or plain text)
 //!
 //! # Modules
 //! - `config`: Configuration management
+//! - `diagram`: DOT, Mermaid and PlantUML exports
+//! - `diff`: Tree comparison (`--diff`, `--snapshot-check`)
+//! - `dupes`: Duplicate file detection (`--dupes`)
+//! - `filetype`: Content-based file type detection
+//! - `hash`: File content digests (`--hash`, `--verify`)
+//! - `heatmap`: Gradient coloring by age, size or depth
+//! - `html`: Self-contained HTML export (`-F html`)
+//! - `icons`: Built-in icon sets
 //! - `ignore`: File ignore patterns handling
+//! - `loc`: Lines-of-code counts (`--loc`)
+//! - `lscolors`: LS_COLORS and dircolors parsing
+//! - `notes`: `.kreenotes` and doc-comment annotations
+//! - `pathlist`: Trees built from path lists (`--fromfile`)
+//! - `readme`: Tree blocks in Markdown files (`--update-readme`)
 //! - `render`: Tree rendering logic
 //! - `search`: Fuzzy search implementation
+//! - `stats`: Tree statistics (`--stats`)
+//! - `style`: Entry styling (colors and text attributes)
+//! - `term`: Terminal capability detection (colors, hyperlinks)
+//! - `theme`: Named color themes and theme files
 //! - `tree`: Directory tree data structure building
 //! - `tui`: Terminal User Interface implementation
 
 mod config;
+mod diagram;
+mod diff;
+mod dupes;
 mod export;
+mod filetype;
+mod hash;
+mod heatmap;
+mod html;
+mod icons;
 mod ignore;
+mod loc;
+mod lscolors;
+mod notes;
+mod pathlist;
+mod readme;
 mod render;
 mod search;
+mod stats;
+mod style;
+mod term;
+mod theme;
 mod tree;
 mod tui;
 
-use std::io;
+use std::io::{self, Read};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
 
 use clap::{CommandFactory, Parser, ValueEnum};
 use clap_complete::Shell;
 
 use config::KreeConfig;
-use export::{export_json, export_markdown, export_yaml};
+use diagram::{export_dot, export_mermaid, export_mermaid_mindmap, export_plantuml};
+use diff::{
+    Compare, count_changes, diff_json, diff_markdown, diff_trees, diff_yaml, format_counts,
+    format_diff, format_verify_counts, retain_changed,
+};
+use dupes::{dupe_tags, dupes_json, dupes_yaml, find_dupes, format_summary, prune_tree};
+use export::{
+    EXPORT_SCHEMA, ExportDocument, ExportNode, MdStyle, ScanOptions, count_entries,
+    export_asciidoc, export_csv, export_json, export_latex, export_markdown, export_markdown_table,
+    export_markdown_tree, export_ndjson, export_org, export_xml, export_yaml, from_export_node,
+    import_document, import_tree, to_export_node,
+};
+use hash::HashAlgorithm;
+use heatmap::{ColorBy, Heatmap};
+use html::export_html;
+use icons::IconSet;
 use ignore::IgnoreFilter;
-use render::{build_color_map, build_icon_map, render_tree};
+use lscolors::LsColors;
+use notes::{Notes, annotate_tree};
+use pathlist::{parse_path_list, tree_from_paths};
+use render::{RenderOptions, build_color_map, build_icon_map, format_tree, render_tree};
 use search::{content_search, fuzzy_search, print_content_results, print_results};
-use tree::{SortMode, TreeOptions, count_max_depth, load_tree};
+use stats::{TreeStats, format_stats, stats_json, stats_yaml};
+use style::{Styler, build_style_rules};
+use term::{ColorLevel, HyperlinkMode};
+use theme::Theme;
+use tree::{
+    SortMode, TreeNode, TreeOptions, compact_tree, count_max_depth, default_depth, filter_tree,
+    load_tree,
+};
 
 /// Output format for tree export.
 #[derive(Clone, ValueEnum)]
@@ -48,6 +107,17 @@ enum ExportFormat {
     Json,
     Yaml,
     Markdown,
+    Latex,
+    Org,
+    Asciidoc,
+    Html,
+    Xml,
+    Csv,
+    Ndjson,
+    Dot,
+    Mermaid,
+    MermaidMindmap,
+    Plantuml,
 }
 
 /// Command Line Interface arguments parser for Kree.
@@ -86,9 +156,27 @@ struct Cli {
     #[arg(long)]
     no_color: bool,
 
-    /// Show Nerd Font icons next to files and directories.
-    #[arg(short = 'i', long)]
-    icons: bool,
+    /// When to wrap names in clickable OSC 8 file:// hyperlinks.
+    #[arg(long, value_enum, value_name = "WHEN")]
+    hyperlink: Option<HyperlinkMode>,
+
+    /// Color theme (e.g. gruvbox-dark, solarized-light) or a theme file name
+    /// from ~/.kree/themes.
+    #[arg(long, value_name = "NAME")]
+    theme: Option<String>,
+
+    /// Show icons next to files and directories, optionally choosing the icon
+    /// set (nerd, emoji, ascii, unicode).
+    #[arg(
+        short = 'i',
+        long,
+        value_enum,
+        value_name = "SET",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "nerd"
+    )]
+    icons: Option<IconSet>,
 
     /// Launch interactive TUI mode for navigation and preview.
     #[arg(short = 't', long)]
@@ -98,11 +186,62 @@ struct Cli {
     #[arg(long)]
     dirs_only: bool,
 
+    /// Also take notes from leading doc comments (`//!`, module docstrings,
+    /// `#` comment headers) for entries missing from `.kreenotes`.
+    #[arg(long)]
+    doc_notes: bool,
+
+    /// Merge chains of directories that contain a single subdirectory
+    /// (e.g. `src/main/java`) into one entry.
+    #[arg(long)]
+    compact: bool,
+
     /// Filter by file extensions (comma-separated, e.g. rs,toml,md).
     /// Directories are always shown to preserve tree structure.
     #[arg(short = 'e', long, value_delimiter = ',')]
     extensions: Vec<String>,
 
+    /// Color names by a gradient of modification time, file size or depth
+    /// instead of by extension.
+    #[arg(long, value_enum, value_name = "MODE")]
+    color_by: Option<ColorBy>,
+
+    /// Detect file types from shebangs, magic bytes and well-known names.
+    /// Detected kinds drive colors, icons and `--extensions` (e.g. `-e py`
+    /// also matches an extensionless Python script).
+    #[arg(long)]
+    detect: bool,
+
+    /// Compute a digest of every file's contents, in parallel, and include
+    /// it in the export. The whole tree is scanned unless `-d` is given.
+    /// See `--verify`.
+    #[arg(long, value_enum, value_name = "ALGO", requires = "format")]
+    hash: Option<HashAlgorithm>,
+
+    /// Count code, comment and blank lines of the files of known languages
+    /// and show them, summed up the tree, as a column with a per-language
+    /// summary. Also included in the exports.
+    #[arg(long, conflicts_with_all = ["diff", "find", "grep", "dupes", "stats"])]
+    loc: bool,
+
+    /// Find files with identical contents and show them in the tree, tagged
+    /// by duplicate set, with the space that removing copies would free.
+    /// Hard links are not duplicates. Searches the whole tree unless `-d`
+    /// is given.
+    #[arg(long, conflicts_with_all = ["fromfile", "load", "diff", "tui", "find", "grep"])]
+    dupes: bool,
+
+    /// Print a summary of the tree instead of the tree: counts by type,
+    /// extension and kind, sizes, the largest, newest and oldest files, a
+    /// depth histogram and empty directories. Covers the whole tree unless
+    /// `-d` is given.
+    #[arg(long, conflicts_with_all = ["diff", "tui", "find", "grep", "dupes"])]
+    stats: bool,
+
+    /// Number of entries in each `--stats` top list.
+    #[arg(long, value_name = "N", default_value_t = 10, requires = "stats")]
+    top: usize,
+
     /// Show file metadata (size, permissions, date, owner).
     #[arg(short = 'l', long)]
     long: bool,
@@ -111,6 +250,16 @@ struct Cli {
     #[arg(short = 'F', long, value_enum)]
     format: Option<ExportFormat>,
 
+    /// Layout of `-F markdown`: a nested list, the box-drawing tree in a
+    /// fenced block, or a table with metadata and note columns.
+    #[arg(long, value_enum, value_name = "STYLE")]
+    md_style: Option<MdStyle>,
+
+    /// With `-F markdown`, link each entry to its path relative to the root
+    /// so the tree is clickable on GitHub.
+    #[arg(long)]
+    md_links: bool,
+
     /// Disable .gitignore rules (by default, .gitignore is respected).
     #[arg(long)]
     no_gitignore: bool,
@@ -123,11 +272,95 @@ struct Cli {
     #[arg(long)]
     man: bool,
 
+    /// Print the JSON Schema of the JSON and YAML exports and exit.
+    #[arg(long)]
+    print_schema: bool,
+
     /// Print the maximum directory depth and exit.
     /// Only counts directories as levels. Useful to discover how deep
     /// the tree goes before rendering with `-d`.
     #[arg(short = 'L', long, conflicts_with_all = ["find", "grep", "tui", "format"])]
     levels: bool,
+
+    /// Regenerate the tree blocks between `<!-- kree:start [OPTIONS] -->`
+    /// and `<!-- kree:end -->` markers in a Markdown file and exit.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["find", "grep", "tui", "format", "levels"]
+    )]
+    update_readme: Option<PathBuf>,
+
+    /// With `--update-readme`, leave the file untouched and exit non-zero if
+    /// any block is out of date.
+    #[arg(long, requires = "update_readme")]
+    check: bool,
+
+    /// Compare two directories and render a merged tree marking entries
+    /// only in DIR_B (`+`), only in DIR_A (`-`) and changed (`~`).
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["DIR_A", "DIR_B"],
+        conflicts_with_all = ["find", "grep", "tui", "levels", "update_readme"]
+    )]
+    diff: Vec<PathBuf>,
+
+    /// Build the tree from a newline- or NUL-separated list of paths instead
+    /// of scanning the disk (`-` reads stdin), e.g. `git ls-files | kree
+    /// --fromfile -`.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["find", "grep", "levels", "diff", "update_readme"]
+    )]
+    fromfile: Option<PathBuf>,
+
+    /// Render a tree previously exported with `-F json` or `-F yaml`
+    /// instead of scanning the disk. Stored metadata is shown with `-l`.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["find", "grep", "levels", "diff", "update_readme", "fromfile"]
+    )]
+    load: Option<PathBuf>,
+
+    /// With `--diff`, hide unchanged entries.
+    #[arg(long, requires = "diff")]
+    changed_only: bool,
+
+    /// With `--diff`, compare file contents instead of modification times.
+    #[arg(long, requires = "diff")]
+    content: bool,
+
+    /// Write the layout of PATH to FILE as a JSON export and exit. The
+    /// whole tree is scanned unless `-d` limits the depth.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["find", "grep", "tui", "format", "levels", "update_readme", "diff", "fromfile", "load"]
+    )]
+    snapshot_save: Option<PathBuf>,
+
+    /// Compare the layout of PATH with a snapshot written by
+    /// `--snapshot-save`, print added, removed and type-changed entries and
+    /// exit non-zero on drift.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["find", "grep", "tui", "format", "levels", "update_readme", "diff", "fromfile", "load", "snapshot_save"]
+    )]
+    snapshot_check: Option<PathBuf>,
+
+    /// Re-hash PATH and compare it with a manifest written by
+    /// `kree -F json --hash ALGO`; exits non-zero on modified, missing or
+    /// extra files.
+    #[arg(
+        long,
+        value_name = "MANIFEST",
+        conflicts_with_all = ["find", "grep", "tui", "format", "levels", "update_readme", "diff", "fromfile", "load", "snapshot_save", "snapshot_check"]
+    )]
+    verify: Option<PathBuf>,
 }
 
 fn main() {
@@ -148,33 +381,102 @@ fn main() {
         return;
     }
 
+    if cli.print_schema {
+        print!("{EXPORT_SCHEMA}");
+        return;
+    }
+
+    // Regenerate README tree blocks; ~/.kreerc is ignored so the output
+    // is reproducible
+    if let Some(file) = &cli.update_readme {
+        process::exit(update_readme_file(file, cli.check));
+    }
+
     // Load configuration from file (e.g., ~/.kreerc)
     let config = KreeConfig::load();
 
+    // Configure colored output: detect what the terminal supports so RGB
+    // colors can be quantized to 256 or 16 colors where needed
+    let no_color = cli.no_color || config.defaults.no_color.unwrap_or(false);
+    let color_level = if no_color {
+        ColorLevel::None
+    } else {
+        ColorLevel::detect()
+    };
+    term::set_color_level(color_level);
+    colored::control::set_override(color_level != ColorLevel::None);
+
+    if let Some(file) = &cli.snapshot_save {
+        process::exit(run_snapshot(file, true, &cli, &config, color_level));
+    }
+    if let Some(file) = &cli.snapshot_check {
+        process::exit(run_snapshot(file, false, &cli, &config, color_level));
+    }
+
+    if let Some(manifest) = &cli.verify {
+        process::exit(run_verify(manifest, &cli.path, &config, color_level));
+    }
+
+    let source = if let Some(file) = &cli.fromfile {
+        match read_path_list(file) {
+            Ok(list) => Source::PathList(list),
+            Err(e) => {
+                eprintln!("Error: cannot read {}: {e}", file.display());
+                process::exit(2);
+            }
+        }
+    } else if let Some(file) = &cli.load {
+        match fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| import_tree(&contents))
+        {
+            Ok(tree) => Source::Loaded(Box::new(from_export_node(tree))),
+            Err(e) => {
+                eprintln!("Error: cannot load {}: {e}", file.display());
+                process::exit(2);
+            }
+        }
+    } else {
+        Source::Disk(cli.path.clone())
+    };
+    let synthetic = source.disk_path().is_none();
+
     // Merge CLI arguments with configuration defaults
-    let depth = if cli.levels && cli.depth.is_none() {
-        // When counting levels without an explicit depth cap, scan fully (up to safety limit)
+    let whole_tree = cli.levels || synthetic || cli.hash.is_some() || cli.loc;
+    let depth = if whole_tree && cli.depth.is_none() {
+        // When counting levels, hashing a manifest, counting lines or
+        // rendering a path list or loaded export without an explicit depth
+        // cap, show everything (up to safety limit)
         60
     } else {
-        cli.depth.or(config.defaults.depth).unwrap_or_else(|| {
-            // Smart default depth: expand more levels for small directories
-            let count = fs::read_dir(&cli.path)
-                .map(|entries| entries.filter_map(|e| e.ok()).count())
-                .unwrap_or(0);
-            if count <= 10 {
-                3
-            } else if count <= 30 {
-                2
-            } else {
-                1
-            }
-        })
+        cli.depth
+            .or(config.defaults.depth)
+            .unwrap_or_else(|| default_depth(&cli.path))
     };
     let sort = cli.sort.or(config.sort_mode()).unwrap_or(SortMode::Kind);
-    let no_color = cli.no_color || config.defaults.no_color.unwrap_or(false);
-    let icons = cli.icons || config.defaults.icons.unwrap_or(false);
+    let icons = cli.icons.is_some() || config.defaults.icons.unwrap_or(false);
+    let icon_set = cli.icons.or(config.icon_set()).unwrap_or_default();
     let all = cli.all || config.defaults.all.unwrap_or(false);
-    let use_gitignore = !cli.no_gitignore && !config.defaults.no_gitignore.unwrap_or(false);
+    let detect = cli.detect || config.defaults.detect.unwrap_or(false);
+    let compact = cli.compact || config.defaults.compact.unwrap_or(false);
+    let doc_notes = cli.doc_notes || config.defaults.doc_notes.unwrap_or(false);
+    let color_by = cli.color_by.or(config.color_by());
+    let md_style = cli.md_style.or(config.md_style()).unwrap_or_default();
+    let md_links = cli.md_links || config.defaults.md_links.unwrap_or(false);
+    // Path lists and loaded exports never read .gitignore files from the disk
+    let use_gitignore =
+        !cli.no_gitignore && !config.defaults.no_gitignore.unwrap_or(false) && !synthetic;
+    let hyperlinks = cli
+        .hyperlink
+        .or(config.hyperlink_mode())
+        .unwrap_or(HyperlinkMode::Auto)
+        .enabled();
+    let theme = cli
+        .theme
+        .as_deref()
+        .or(config.defaults.theme.as_deref())
+        .map(Theme::load)
+        .unwrap_or_default();
     let opts = TreeOptions {
         dirs_only: cli.dirs_only,
         extensions: cli
@@ -182,13 +484,18 @@ fn main() {
             .iter()
             .map(|e| e.trim_start_matches('.').to_lowercase())
             .collect(),
-        show_metadata: cli.long,
+        // Heatmaps need sizes and times even when they aren't printed
+        show_metadata: cli.long
+            || (cli.format.is_none() && color_by.is_some_and(ColorBy::needs_metadata)),
+        detect_kinds: detect,
+        compact,
+        hash: cli.hash,
+        loc: cli.loc,
     };
 
-    // Configure colored output
-    if no_color {
-        colored::control::set_override(false);
-    }
+    // Doc comments of path lists and loaded exports would be read from
+    // whatever files happen to exist under the same names
+    let notes = Notes::load(&cli.path, doc_notes && !synthetic);
 
     // Safety check for depth to prevent stack overflow or excessive output
     if depth > 60 {
@@ -200,27 +507,142 @@ fn main() {
     if cli.levels {
         let filter =
             IgnoreFilter::with_gitignore(!all, &config.ignore.patterns, use_gitignore, &cli.path);
-        let max = count_max_depth(&cli.path, depth, 0, &filter, &opts);
+        let max = count_max_depth(&cli.path, depth, 0, &filter);
         println!("{max}");
         return;
     }
 
+    // Compare two directories
+    if let [dir_a, dir_b] = cli.diff.as_slice() {
+        let opts = TreeOptions {
+            show_metadata: true,
+            compact: false,
+            ..opts
+        };
+        let depth = cli.depth.unwrap_or(60);
+        let load = |path: &PathBuf| {
+            let filter =
+                IgnoreFilter::with_gitignore(!all, &config.ignore.patterns, use_gitignore, path);
+            to_export_node(&load_tree(path, depth, 0, &filter, sort, &opts))
+        };
+        let compare = if cli.content {
+            Compare::Content
+        } else {
+            Compare::Metadata
+        };
+        let mut diff = diff_trees(&load(dir_a), &load(dir_b), compare);
+        diff.name = format!("{} → {}", dir_a.display(), dir_b.display());
+        if cli.changed_only {
+            retain_changed(&mut diff);
+        }
+        let output = match &cli.format {
+            Some(ExportFormat::Json) => diff_json(&diff),
+            Some(ExportFormat::Yaml) => diff_yaml(&diff),
+            Some(ExportFormat::Markdown) => diff_markdown(&diff),
+            Some(format) => {
+                let name = format.to_possible_value().expect("no skipped formats");
+                eprintln!("Error: -F {} is not supported with --diff", name.get_name());
+                process::exit(2);
+            }
+            None => format!(
+                "{}\n{}\n",
+                format_diff(&diff, false, color_level != ColorLevel::None),
+                format_counts(&count_changes(&diff))
+            ),
+        };
+        print!("{output}");
+        return;
+    }
+
+    // Find duplicate files
+    if cli.dupes {
+        let depth = cli.depth.unwrap_or(60);
+        let filter =
+            IgnoreFilter::with_gitignore(!all, &config.ignore.patterns, use_gitignore, &cli.path);
+        let mut root = load_tree(&cli.path, depth, 0, &filter, sort, &opts);
+        let groups = find_dupes(&root);
+        let output = match &cli.format {
+            Some(ExportFormat::Json) => dupes_json(&groups),
+            Some(ExportFormat::Yaml) => dupes_yaml(&groups),
+            Some(format) => {
+                let name = format.to_possible_value().expect("no skipped formats");
+                eprintln!(
+                    "Error: -F {} is not supported with --dupes",
+                    name.get_name()
+                );
+                process::exit(2);
+            }
+            None if groups.is_empty() => format!("{}\n", format_summary(&groups)),
+            None => {
+                let tags = dupe_tags(&groups);
+                prune_tree(&mut root, &tags);
+                let styler = build_styler(&config, &theme, synthetic);
+                let icon_map = if icons {
+                    Some(build_icon_map(&theme.icons_with(&config.icons), icon_set))
+                } else {
+                    None
+                };
+                let render_opts = RenderOptions {
+                    hyperlinks,
+                    show_metadata: cli.long,
+                    tags,
+                    ..RenderOptions::default()
+                };
+                let tree = format_tree(&root, &styler, icon_map.as_ref(), &render_opts);
+                format!("{tree}{}\n", format_summary(&groups))
+            }
+        };
+        print!("{output}");
+        return;
+    }
+
+    // Summarize the tree
+    if cli.stats {
+        let depth = cli.depth.unwrap_or(60);
+        let opts = TreeOptions {
+            show_metadata: true,
+            ..opts
+        };
+        let filter =
+            IgnoreFilter::with_gitignore(!all, &config.ignore.patterns, use_gitignore, &cli.path);
+        let root = build_tree(source, depth, &filter, sort, &opts, &notes);
+        let stats = TreeStats::collect(&root, depth, cli.top);
+        let output = match &cli.format {
+            Some(ExportFormat::Json) => stats_json(&stats),
+            Some(ExportFormat::Yaml) => stats_yaml(&stats),
+            Some(format) => {
+                let name = format.to_possible_value().expect("no skipped formats");
+                eprintln!(
+                    "Error: -F {} is not supported with --stats",
+                    name.get_name()
+                );
+                process::exit(2);
+            }
+            None => format_stats(&stats, color_level != ColorLevel::None),
+        };
+        print!("{output}");
+        return;
+    }
+
     // Run in TUI mode if requested
     if cli.tui {
         let filter =
             IgnoreFilter::with_gitignore(!all, &config.ignore.patterns, use_gitignore, &cli.path);
-        let color_map = build_color_map(&config.colors);
-        let icon_map = build_icon_map(&config.icons);
-        let root = load_tree(&cli.path, depth, 0, &filter, sort, &opts);
+        let styler = build_styler(&config, &theme, synthetic);
+        let icon_map = build_icon_map(&theme.icons_with(&config.icons), icon_set);
+        let root_path = source.disk_path();
+        let root = build_tree(source, depth, &filter, sort, &opts, &notes);
         if let Err(e) = tui::run(
             root,
-            cli.path.clone(),
-            color_map,
+            root_path,
+            styler,
             icon_map,
             filter,
             sort,
             depth,
             opts,
+            notes,
+            color_by,
         ) {
             eprintln!("TUI error: {e}");
             process::exit(1);
@@ -229,7 +651,7 @@ fn main() {
     // Run content search if --grep is provided
     else if let Some(query) = &cli.grep {
         let results = content_search(&cli.path, query, depth);
-        print_content_results(&results);
+        print_content_results(&results, hyperlinks);
     }
     // Run fuzzy search if a query is provided
     else if let Some(query) = &cli.find {
@@ -240,11 +662,43 @@ fn main() {
     else if let Some(format) = &cli.format {
         let filter =
             IgnoreFilter::with_gitignore(!all, &config.ignore.patterns, use_gitignore, &cli.path);
-        let root = load_tree(&cli.path, depth, 0, &filter, sort, &opts);
+        let root_path = source.disk_path().and_then(|p| fs::canonicalize(p).ok());
+        let root = build_tree(source, depth, &filter, sort, &opts, &notes);
+        let document = || {
+            let options = scan_options(depth, sort, all, use_gitignore, &opts);
+            ExportDocument::new(&root, root_path.as_deref(), options)
+        };
+        let icon_map = if icons {
+            Some(build_icon_map(&theme.icons_with(&config.icons), icon_set))
+        } else {
+            None
+        };
+        let styler = build_styler(&config, &theme, synthetic);
         let output = match format {
-            ExportFormat::Json => export_json(&root),
-            ExportFormat::Yaml => export_yaml(&root),
-            ExportFormat::Markdown => export_markdown(&root),
+            ExportFormat::Json => export_json(&document()),
+            ExportFormat::Yaml => export_yaml(&document()),
+            ExportFormat::Markdown => match md_style {
+                MdStyle::List => export_markdown(&root, md_links),
+                MdStyle::Table => export_markdown_table(&root, md_links),
+                MdStyle::Tree => {
+                    let render_opts = RenderOptions {
+                        show_metadata: cli.long,
+                        ..RenderOptions::default()
+                    };
+                    export_markdown_tree(&root, &styler, icon_map.as_ref(), &render_opts)
+                }
+            },
+            ExportFormat::Latex => export_latex(&root),
+            ExportFormat::Org => export_org(&root),
+            ExportFormat::Asciidoc => export_asciidoc(&root),
+            ExportFormat::Xml => export_xml(&root),
+            ExportFormat::Csv => export_csv(&root),
+            ExportFormat::Ndjson => export_ndjson(&root),
+            ExportFormat::Html => export_html(&root, &styler, icon_map.as_ref()),
+            ExportFormat::Dot => export_dot(&root, &styler),
+            ExportFormat::Mermaid => export_mermaid(&root, &styler),
+            ExportFormat::MermaidMindmap => export_mermaid_mindmap(&root),
+            ExportFormat::Plantuml => export_plantuml(&root, &styler),
         };
         print!("{output}");
     }
@@ -252,13 +706,385 @@ fn main() {
     else {
         let filter =
             IgnoreFilter::with_gitignore(!all, &config.ignore.patterns, use_gitignore, &cli.path);
-        let color_map = build_color_map(&config.colors);
+        let styler = build_styler(&config, &theme, synthetic);
         let icon_map = if icons {
-            Some(build_icon_map(&config.icons))
+            Some(build_icon_map(&theme.icons_with(&config.icons), icon_set))
         } else {
             None
         };
-        let root = load_tree(&cli.path, depth, 0, &filter, sort, &opts);
-        render_tree(&root, &color_map, icon_map.as_ref());
+        let root = build_tree(source, depth, &filter, sort, &opts, &notes);
+        let render_opts = RenderOptions {
+            hyperlinks,
+            show_metadata: cli.long,
+            heatmap: color_by.map(|mode| Heatmap::new(mode, &root)),
+            show_loc: cli.loc,
+            ..RenderOptions::default()
+        };
+        render_tree(&root, &styler, icon_map.as_ref(), &render_opts);
+    }
+}
+
+/// Where the tree is read from.
+enum Source {
+    /// Scan a directory.
+    Disk(PathBuf),
+    /// Build from a list of paths (`--fromfile`) without touching the disk.
+    PathList(String),
+    /// Re-render a tree read from an export (`--load`).
+    Loaded(Box<TreeNode>),
+}
+
+impl Source {
+    /// Returns the scanned directory, if the tree comes from the disk.
+    fn disk_path(&self) -> Option<PathBuf> {
+        match self {
+            Source::Disk(path) => Some(path.clone()),
+            Source::PathList(_) | Source::Loaded(_) => None,
+        }
+    }
+}
+
+/// Reads a `--fromfile` path list from a file, or from stdin for `-`.
+fn read_path_list(file: &Path) -> io::Result<String> {
+    let bytes = if file == Path::new("-") {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        bytes
+    } else {
+        fs::read(file)?
+    };
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Loads the tree and applies the post-processing passes: notes, then
+/// `--compact` chain merging. Loaded exports keep their stored notes.
+fn build_tree(
+    source: Source,
+    depth: u32,
+    filter: &IgnoreFilter,
+    sort: SortMode,
+    opts: &TreeOptions,
+    notes: &Notes,
+) -> TreeNode {
+    let mut root = match source {
+        Source::Disk(path) => {
+            let mut root = load_tree(&path, depth, 0, filter, sort, opts);
+            annotate_tree(&mut root, notes);
+            root
+        }
+        Source::PathList(list) => {
+            let mut root = tree_from_paths(&parse_path_list(&list), depth, filter, sort, opts);
+            annotate_tree(&mut root, notes);
+            root
+        }
+        Source::Loaded(mut root) => {
+            filter_tree(&mut root, depth, filter, sort, opts);
+            *root
+        }
+    };
+    if opts.compact {
+        compact_tree(&mut root);
+    }
+    if opts.loc {
+        loc::sum_tree(&mut root);
+    }
+    root
+}
+
+/// Runs `--update-readme` and returns the process exit code.
+fn update_readme_file(file: &PathBuf, check: bool) -> i32 {
+    let contents = match fs::read_to_string(file) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("Error: cannot read {}: {e}", file.display());
+            return 2;
+        }
+    };
+    let base_dir = file.parent().filter(|p| !p.as_os_str().is_empty());
+    let base_dir = base_dir.unwrap_or_else(|| Path::new("."));
+    let update = match readme::update_readme(&contents, base_dir) {
+        Ok(update) => update,
+        Err(e) => {
+            eprintln!("Error: {}: {e}", file.display());
+            return 2;
+        }
+    };
+
+    if update.stale.is_empty() {
+        println!("{} is up to date", file.display());
+        return 0;
+    }
+    if check {
+        for line in &update.stale {
+            eprintln!("{}:{line}: tree block is out of date", file.display());
+        }
+        eprintln!("Run `kree --update-readme {}` to refresh it", file.display());
+        return 1;
+    }
+    if let Err(e) = fs::write(file, &update.contents) {
+        eprintln!("Error: cannot write {}: {e}", file.display());
+        return 2;
+    }
+    println!("Updated {} tree block(s) in {}", update.stale.len(), file.display());
+    0
+}
+
+/// Runs `--snapshot-save FILE` (`save`) or `--snapshot-check FILE` and
+/// returns the process exit code.
+fn run_snapshot(
+    file: &Path,
+    save: bool,
+    cli: &Cli,
+    config: &KreeConfig,
+    color_level: ColorLevel,
+) -> i32 {
+    let all = cli.all || config.defaults.all.unwrap_or(false);
+    let use_gitignore = !cli.no_gitignore && !config.defaults.no_gitignore.unwrap_or(false);
+    let depth = cli.depth.unwrap_or(60);
+    if save {
+        let root = scan_snapshot_tree(file, cli, config, depth, all, use_gitignore);
+        let root_path = fs::canonicalize(&cli.path).ok();
+        let options = scan_options(
+            depth,
+            SortMode::Kind,
+            all,
+            use_gitignore,
+            &TreeOptions::default(),
+        );
+        let document = ExportDocument::new(&root, root_path.as_deref(), options);
+        if let Err(e) = fs::write(file, export_json(&document) + "\n") {
+            eprintln!("Error: cannot write {}: {e}", file.display());
+            return 2;
+        }
+        println!("Saved layout of {} to {}", cli.path.display(), file.display());
+        return 0;
+    }
+
+    let (snapshot, options) = match fs::read_to_string(file)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| import_document(&contents))
+    {
+        Ok(document) => document,
+        Err(e) => {
+            eprintln!("Error: cannot read snapshot {}: {e}", file.display());
+            return 2;
+        }
+    };
+    // Rescan the way the snapshot was taken, so that a different command
+    // line does not show up as drift
+    let root = match options {
+        Some(options) => {
+            let filter = IgnoreFilter::with_gitignore(
+                !options.all,
+                &config.ignore.patterns,
+                options.gitignore,
+                &cli.path,
+            );
+            let opts = TreeOptions {
+                dirs_only: options.dirs_only,
+                extensions: options.extensions,
+                detect_kinds: options.detect,
+                compact: options.compact,
+                ..TreeOptions::default()
+            };
+            let mut root = load_tree(&cli.path, options.depth, 0, &filter, SortMode::Kind, &opts);
+            if opts.compact {
+                compact_tree(&mut root);
+            }
+            remove_snapshot(&mut root, file);
+            root
+        }
+        None => scan_snapshot_tree(file, cli, config, depth, all, use_gitignore),
+    };
+    let mut diff = diff_trees(&snapshot, &to_export_node(&root), Compare::Structure);
+    if !diff.has_changes() {
+        println!("{} matches {}", cli.path.display(), file.display());
+        return 0;
+    }
+    retain_changed(&mut diff);
+    print!(
+        "{}",
+        format_diff(&diff, true, color_level != ColorLevel::None)
+    );
+    println!("\n{}", format_counts(&count_changes(&diff)));
+    1
+}
+
+/// Scans `cli.path` for a snapshot with the settings of the command line.
+fn scan_snapshot_tree(
+    file: &Path,
+    cli: &Cli,
+    config: &KreeConfig,
+    depth: u32,
+    all: bool,
+    use_gitignore: bool,
+) -> TreeNode {
+    let filter =
+        IgnoreFilter::with_gitignore(!all, &config.ignore.patterns, use_gitignore, &cli.path);
+    let opts = TreeOptions::default();
+    let mut root = load_tree(&cli.path, depth, 0, &filter, SortMode::Kind, &opts);
+    remove_snapshot(&mut root, file);
+    root
+}
+
+/// Removes the snapshot file from a tree when it lives inside it.
+fn remove_snapshot(root: &mut TreeNode, file: &Path) {
+    if let Some(snapshot_path) = absolute_file_path(file) {
+        remove_entry(root, &snapshot_path);
+    }
+}
+
+/// Runs `--verify MANIFEST` on `path` and returns the process exit code.
+fn run_verify(
+    manifest_path: &Path,
+    path: &PathBuf,
+    config: &KreeConfig,
+    color_level: ColorLevel,
+) -> i32 {
+    let (mut manifest, options) = match fs::read_to_string(manifest_path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| import_document(&contents))
+    {
+        Ok(document) => document,
+        Err(e) => {
+            eprintln!(
+                "Error: cannot read manifest {}: {e}",
+                manifest_path.display()
+            );
+            return 2;
+        }
+    };
+    let Some((options, algorithm)) = options.and_then(|options| {
+        let algorithm = HashAlgorithm::from_name(options.hash.as_deref()?)?;
+        Some((options, algorithm))
+    }) else {
+        eprintln!(
+            "Error: {} has no content hashes; write it with `kree -F json --hash sha256`",
+            manifest_path.display()
+        );
+        return 2;
+    };
+
+    let filter = IgnoreFilter::with_gitignore(
+        !options.all,
+        &config.ignore.patterns,
+        options.gitignore,
+        path,
+    );
+    let opts = TreeOptions {
+        dirs_only: options.dirs_only,
+        extensions: options.extensions,
+        detect_kinds: options.detect,
+        compact: options.compact,
+        hash: Some(algorithm),
+        ..TreeOptions::default()
+    };
+    let mut root = load_tree(path, options.depth, 0, &filter, SortMode::Kind, &opts);
+    if opts.compact {
+        compact_tree(&mut root);
+    }
+    let mut current = to_export_node(&root);
+    // A manifest kept inside the tree it describes is not part of it
+    if let (Ok(file), Ok(base)) = (fs::canonicalize(manifest_path), fs::canonicalize(path))
+        && let Ok(relative) = file.strip_prefix(&base)
+    {
+        remove_relative(&mut manifest, relative);
+        remove_relative(&mut current, relative);
+    }
+
+    let mut diff = diff_trees(&manifest, &current, Compare::Hash);
+    if !diff.has_changes() {
+        let (_, files) = count_entries(&current);
+        println!(
+            "{} matches {} ({files} files)",
+            path.display(),
+            manifest_path.display()
+        );
+        return 0;
+    }
+    retain_changed(&mut diff);
+    print!(
+        "{}",
+        format_diff(&diff, true, color_level != ColorLevel::None)
+    );
+    println!("\n{}", format_verify_counts(&count_changes(&diff)));
+    1
+}
+
+/// Removes the entry at `relative`, a path below the root, from an export.
+fn remove_relative(node: &mut ExportNode, relative: &Path) {
+    let mut components = relative.iter();
+    let Some(name) = components.next() else {
+        return;
+    };
+    let rest = components.as_path();
+    if rest.as_os_str().is_empty() {
+        node.children.retain(|child| child.name.as_str() != name);
+    } else if let Some(child) = node.children.iter_mut().find(|c| c.name.as_str() == name) {
+        remove_relative(child, rest);
+    }
+}
+
+/// Collects the scan settings recorded in JSON and YAML exports.
+fn scan_options(
+    depth: u32,
+    sort: SortMode,
+    all: bool,
+    gitignore: bool,
+    opts: &TreeOptions,
+) -> ScanOptions {
+    ScanOptions {
+        depth,
+        sort: sort
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default(),
+        all,
+        gitignore,
+        dirs_only: opts.dirs_only,
+        extensions: opts.extensions.clone(),
+        long: opts.show_metadata,
+        detect: opts.detect_kinds,
+        compact: opts.compact,
+        hash: opts.hash.map(|algorithm| algorithm.name().to_string()),
+        loc: opts.loc,
+    }
+}
+
+/// Returns the canonical path of `file`, which need not exist yet.
+fn absolute_file_path(file: &Path) -> Option<PathBuf> {
+    let parent = file.parent().filter(|p| !p.as_os_str().is_empty());
+    let parent = fs::canonicalize(parent.unwrap_or_else(|| Path::new("."))).ok()?;
+    Some(parent.join(file.file_name()?))
+}
+
+/// Removes the entry at the canonical path `target` from the tree.
+fn remove_entry(node: &mut TreeNode, target: &Path) {
+    node.children.retain(|child| {
+        Some(child.name.as_str()) != target.file_name().and_then(|n| n.to_str())
+            || fs::canonicalize(&child.path).ok().as_deref() != Some(target)
+    });
+    for child in &mut node.children {
+        remove_entry(child, target);
+    }
+}
+
+/// Builds the entry `Styler` from the configuration and the active theme.
+///
+/// User colors and style rules take precedence over the theme's. Entries of
+/// `synthetic` trees (path lists, loaded exports) are not looked up on disk.
+fn build_styler(config: &KreeConfig, theme: &Theme, synthetic: bool) -> Styler {
+    let mut rules = build_style_rules(&config.styles);
+    rules.extend(build_style_rules(&theme.styles));
+    let styler = Styler::new(
+        build_color_map(&theme.colors_with(&config.colors)),
+        LsColors::load(&config.ls_colors),
+        rules,
+        theme.ui.clone(),
+    );
+    if synthetic {
+        styler.without_disk_lookups()
+    } else {
+        styler
     }
 }
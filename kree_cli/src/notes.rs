@@ -0,0 +1,275 @@
+//! Per-entry notes rendered as tree comments.
+//!
+//! Notes come from a `.kreenotes` file in the scanned root and, optionally,
+//! from the leading doc comment of each file (`--doc-notes`).
+//!
+//! `.kreenotes` uses the same layout people hand-write in READMEs:
+//!
+//! ```text
+//! # comments and blank lines are ignored
+//! src/            # application source
+//! src/main.rs     # CLI entry point
+//! ```
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+use crate::filetype;
+use crate::loc;
+use crate::tree::TreeNode;
+
+/// Name of the notes file looked up in the scanned root.
+pub const NOTES_FILE: &str = ".kreenotes";
+
+/// Number of bytes read from the start of a file to find its doc comment.
+const DOC_SCAN_LEN: u64 = 4096;
+
+/// Files whose doc comment describes the directory containing them.
+const DIR_DOC_FILES: &[&str] = &["mod.rs", "lib.rs", "__init__.py"];
+
+/// Notes for the entries under one root.
+#[derive(Debug, Default)]
+pub struct Notes {
+    root: PathBuf,
+    /// Notes keyed by path relative to `root`.
+    entries: HashMap<PathBuf, String>,
+    /// Fall back to leading doc comments for entries without a note.
+    from_docs: bool,
+}
+
+impl Notes {
+    /// Loads `.kreenotes` from `root`, if present.
+    pub fn load(root: &Path, from_docs: bool) -> Self {
+        let entries = std::fs::read_to_string(root.join(NOTES_FILE))
+            .map(|contents| parse(&contents))
+            .unwrap_or_default();
+        Notes {
+            root: root.to_path_buf(),
+            entries,
+            from_docs,
+        }
+    }
+
+    /// Returns true if no note can ever be found.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && !self.from_docs
+    }
+
+    /// Returns the note for the entry at `path`, a directory if `is_dir`.
+    pub fn note_for(&self, path: &Path, is_dir: bool) -> Option<String> {
+        if let Ok(relative) = path.strip_prefix(&self.root)
+            && let Some(note) = self.entries.get(&normalize(relative))
+        {
+            return Some(note.clone());
+        }
+
+        if !self.from_docs {
+            return None;
+        }
+        if is_dir {
+            DIR_DOC_FILES
+                .iter()
+                .find_map(|name| doc_comment(&path.join(name)))
+        } else {
+            doc_comment(path)
+        }
+    }
+}
+
+/// Sets the `note` of every node in the tree.
+pub fn annotate_tree(node: &mut TreeNode, notes: &Notes) {
+    if notes.is_empty() {
+        return;
+    }
+    node.note = notes.note_for(&node.path, node.is_dir);
+    for child in &mut node.children {
+        annotate_tree(child, notes);
+    }
+}
+
+/// Parses `.kreenotes` contents into a map of normalized paths to notes.
+fn parse(contents: &str) -> HashMap<PathBuf, String> {
+    let mut entries = HashMap::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((path, note)) = line.split_once(" #") else {
+            continue;
+        };
+        let note = note.trim();
+        if !note.is_empty() {
+            entries.insert(normalize(Path::new(path.trim())), note.to_string());
+        }
+    }
+    entries
+}
+
+/// Drops `.` components and trailing slashes so `./src/` and `src` match.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+/// Extracts the first line of a file's leading doc comment.
+///
+/// Recognizes Rust inner doc comments (`//!`), Python module docstrings and
+/// `#` comment headers, skipping a shebang line. `#` headers only count in
+/// languages where `#` starts a comment, so C preprocessor lines and
+/// Markdown titles are not taken for notes.
+fn doc_comment(path: &Path) -> Option<String> {
+    if !path.metadata().ok()?.is_file() {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(DOC_SCAN_LEN)
+        .read_to_end(&mut bytes)
+        .ok()?;
+    // The scan may end inside a multibyte character
+    let head = String::from_utf8_lossy(&bytes);
+
+    let mut lines = head
+        .lines()
+        .map(str::trim)
+        .skip_while(|l| l.starts_with("#!"))
+        .skip_while(|l| l.is_empty());
+    let first = lines.next()?;
+
+    let text = if let Some(rest) = first.strip_prefix("//!") {
+        std::iter::once(rest)
+            .chain(lines.map_while(|l| l.strip_prefix("//!")))
+            .map(str::trim)
+            .find(|l| !l.is_empty())?
+    } else if let Some(rest) = first
+        .strip_prefix("\"\"\"")
+        .or_else(|| first.strip_prefix("'''"))
+    {
+        std::iter::once(rest)
+            .chain(lines)
+            .map(|l| l.trim().trim_end_matches("\"\"\"").trim_end_matches("'''"))
+            .find(|l| !l.is_empty())?
+    } else if first.starts_with('#')
+        && !first.starts_with("#[")
+        && filetype::detect(path).is_some_and(|kind| loc::line_comments(kind).contains(&"#"))
+    {
+        std::iter::once(first)
+            .chain(lines.take_while(|l| l.starts_with('#')))
+            .map(|l| l.trim_start_matches('#').trim())
+            .find(|l| !l.is_empty() && !l.contains("-*-"))?
+    } else {
+        return None;
+    };
+
+    Some(text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn parse_kreenotes() {
+        let entries =
+            parse("# header\n\n./src/   # application source\nREADME.md # docs\nno-note\n");
+        assert_eq!(
+            entries.get(Path::new("src")).map(String::as_str),
+            Some("application source")
+        );
+        assert_eq!(
+            entries.get(Path::new("README.md")).map(String::as_str),
+            Some("docs")
+        );
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[test]
+    fn doc_comment_styles() {
+        let dir = tempdir().unwrap();
+        let rust = dir.path().join("lib.rs");
+        fs::write(
+            &rust,
+            "//! Tree rendering.\n//!\n//! More text.\nuse std::fs;\n",
+        )
+        .unwrap();
+        let python = dir.path().join("tool.py");
+        fs::write(
+            &python,
+            "#!/usr/bin/env python\n\"\"\"\nBuild helper.\n\"\"\"\n",
+        )
+        .unwrap();
+        let shell = dir.path().join("deploy.sh");
+        fs::write(&shell, "#!/bin/sh\n# Deploys the site.\nset -e\n").unwrap();
+        let plain = dir.path().join("plain.rs");
+        fs::write(&plain, "#[derive(Debug)]\nstruct A;\n").unwrap();
+
+        assert_eq!(doc_comment(&rust).as_deref(), Some("Tree rendering."));
+        assert_eq!(doc_comment(&python).as_deref(), Some("Build helper."));
+        assert_eq!(doc_comment(&shell).as_deref(), Some("Deploys the site."));
+        assert_eq!(doc_comment(&plain), None);
+    }
+
+    #[test]
+    fn hash_headers_need_hash_comments() {
+        let dir = tempdir().unwrap();
+        let c = dir.path().join("main.c");
+        fs::write(&c, "#include <stdio.h>\n").unwrap();
+        let header = dir.path().join("a.h");
+        fs::write(&header, "#pragma once\n").unwrap();
+        let readme = dir.path().join("README.md");
+        fs::write(&readme, "# Title\n").unwrap();
+        let script = dir.path().join("deploy");
+        fs::write(&script, "#!/bin/sh\n# Deploys the site.\n").unwrap();
+
+        assert_eq!(doc_comment(&c), None);
+        assert_eq!(doc_comment(&header), None);
+        assert_eq!(doc_comment(&readme), None);
+        assert_eq!(doc_comment(&script).as_deref(), Some("Deploys the site."));
+    }
+
+    #[test]
+    fn scan_may_split_a_character() {
+        let dir = tempdir().unwrap();
+        let rust = dir.path().join("lib.rs");
+        // The 4096-byte scan ends in the middle of an `é`
+        let contents = format!("//! Doc.\n{}", "é".repeat(3000));
+        assert!(!contents.is_char_boundary(DOC_SCAN_LEN as usize));
+        fs::write(&rust, contents).unwrap();
+        assert_eq!(doc_comment(&rust).as_deref(), Some("Doc."));
+    }
+
+    #[test]
+    fn kreenotes_take_precedence_over_docs() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(NOTES_FILE), "main.rs  # entry point\n").unwrap();
+        fs::write(dir.path().join("main.rs"), "//! Ignored doc.\n").unwrap();
+        fs::create_dir(dir.path().join("pkg")).unwrap();
+        fs::write(
+            dir.path().join("pkg/__init__.py"),
+            "\"\"\"Package docs.\"\"\"\n",
+        )
+        .unwrap();
+
+        let notes = Notes::load(dir.path(), true);
+        assert_eq!(
+            notes
+                .note_for(&dir.path().join("main.rs"), false)
+                .as_deref(),
+            Some("entry point")
+        );
+        assert_eq!(
+            notes.note_for(&dir.path().join("pkg"), true).as_deref(),
+            Some("Package docs.")
+        );
+
+        let without_docs = Notes::load(dir.path(), false);
+        assert_eq!(without_docs.note_for(&dir.path().join("pkg"), true), None);
+    }
+}
@@ -0,0 +1,173 @@
+//! Trees built from path lists (`--fromfile`).
+//!
+//! Reads the output of `git ls-files`, `find`, `tar -tf`, `rg --files` and
+//! similar tools and turns it into a `TreeNode` without touching the disk.
+//! An entry is a directory if other entries are listed below it or if it is
+//! written with a trailing `/`.
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::filetype;
+use crate::ignore::IgnoreFilter;
+use crate::tree::{SortMode, TreeNode, TreeOptions, filter_tree};
+
+/// Splits a path list on NUL bytes (`find -print0`, `git ls-files -z`) if
+/// it contains any, otherwise on newlines. Empty entries are dropped.
+pub fn parse_path_list(input: &str) -> Vec<&str> {
+    let separator = if input.contains('\0') { '\0' } else { '\n' };
+    input
+        .split(separator)
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Intermediate tree used while inserting paths in any order.
+#[derive(Default)]
+struct Entry {
+    is_dir: bool,
+    children: BTreeMap<String, Entry>,
+}
+
+/// Builds a tree from listed paths.
+///
+/// The root is `/` when every path is absolute and `.` otherwise. The depth
+/// limit, `filter`, `sort` and the dirs-only and extension options apply as
+/// when scanning a directory, through `filter_tree`.
+pub fn tree_from_paths(
+    paths: &[&str],
+    max_depth: u32,
+    filter: &IgnoreFilter,
+    sort: SortMode,
+    opts: &TreeOptions,
+) -> TreeNode {
+    let absolute = !paths.is_empty() && paths.iter().all(|p| Path::new(p).is_absolute());
+    let root_path = PathBuf::from(if absolute { "/" } else { "." });
+
+    let mut root = Entry {
+        is_dir: true,
+        ..Entry::default()
+    };
+    for path in paths {
+        let names: Vec<String> = Path::new(path)
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                Component::ParentDir => Some("..".to_string()),
+                _ => None,
+            })
+            .collect();
+
+        let mut entry = &mut root;
+        for name in names {
+            entry.is_dir = true;
+            entry = entry.children.entry(name).or_default();
+        }
+        if path.ends_with('/') {
+            entry.is_dir = true;
+        }
+    }
+
+    let name = root_path.display().to_string();
+    let mut tree = to_tree_node(name, root_path, root, opts.detect_kinds);
+    filter_tree(&mut tree, max_depth, filter, sort, opts);
+    tree
+}
+
+fn to_tree_node(name: String, path: PathBuf, entry: Entry, detect_kinds: bool) -> TreeNode {
+    let file_kind = if detect_kinds && !entry.is_dir {
+        filetype::from_extension(&path)
+    } else {
+        None
+    };
+    let children = entry
+        .children
+        .into_iter()
+        .map(|(child_name, child)| {
+            let child_path = path.join(&child_name);
+            to_tree_node(child_name, child_path, child, detect_kinds)
+        })
+        .collect();
+
+    TreeNode {
+        name,
+        path,
+        is_dir: entry.is_dir,
+        children,
+        file_kind,
+        ..TreeNode::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(input: &str, opts: &TreeOptions) -> TreeNode {
+        let filter = IgnoreFilter::with_gitignore(true, &[], false, Path::new("/nonexistent"));
+        tree_from_paths(&parse_path_list(input), 10, &filter, SortMode::Kind, opts)
+    }
+
+    fn names(node: &TreeNode) -> Vec<&str> {
+        node.children.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn newline_and_nul_separated_lists() {
+        assert_eq!(parse_path_list("a\r\nb\n\nc\n"), vec!["a", "b", "c"]);
+        assert_eq!(parse_path_list("a b\0c\nd\0"), vec!["a b", "c\nd"]);
+    }
+
+    #[test]
+    fn builds_directories_from_paths() {
+        let tree = build(
+            "./src/main.rs\nREADME.md\nsrc/lib.rs\nempty/\n.git/HEAD\n",
+            &TreeOptions::default(),
+        );
+        assert_eq!(tree.name, ".");
+        assert!(tree.is_dir);
+        assert_eq!(names(&tree), vec!["empty", "src", "README.md"]);
+        assert!(tree.children[0].is_dir);
+        assert!(tree.children[0].children.is_empty());
+        assert_eq!(names(&tree.children[1]), vec!["lib.rs", "main.rs"]);
+        assert_eq!(tree.children[1].children[0].path, Path::new("./src/lib.rs"));
+        assert!(!tree.children[2].is_dir);
+    }
+
+    #[test]
+    fn applies_tree_options() {
+        let opts = TreeOptions {
+            extensions: vec!["rs".to_string()],
+            ..TreeOptions::default()
+        };
+        let tree = build("src/main.rs\nsrc/notes.txt\ndocs/guide.md\n", &opts);
+        assert_eq!(names(&tree), vec!["docs", "src"]);
+        assert_eq!(names(&tree.children[1]), vec!["main.rs"]);
+
+        let dirs_only = TreeOptions {
+            dirs_only: true,
+            ..TreeOptions::default()
+        };
+        assert_eq!(names(&build("a/b.txt\nc.txt\n", &dirs_only)), vec!["a"]);
+    }
+
+    #[test]
+    fn ignore_patterns_match_relative_paths() {
+        let patterns = ["target/debug".to_string(), "*.log".to_string()];
+        let filter =
+            IgnoreFilter::with_gitignore(true, &patterns, false, Path::new("/nonexistent"));
+        let paths = parse_path_list("target/debug/app\ntarget/release/app\nlogs/run.log\n");
+        let tree = tree_from_paths(&paths, 10, &filter, SortMode::Kind, &TreeOptions::default());
+        assert_eq!(names(&tree), vec!["logs", "target"]);
+        assert!(tree.children[0].children.is_empty());
+        assert_eq!(names(&tree.children[1]), vec!["release"]);
+    }
+
+    #[test]
+    fn absolute_paths_use_filesystem_root() {
+        let tree = build("/etc/hosts\n/etc/passwd\n", &TreeOptions::default());
+        assert_eq!(tree.name, "/");
+        assert_eq!(tree.children[0].path, Path::new("/etc"));
+    }
+}
@@ -0,0 +1,313 @@
+//! Keeps tree blocks in Markdown files up to date (`--update-readme`).
+//!
+//! A block is delimited by HTML comments; the start marker carries the kree
+//! options used to render it:
+//!
+//! ````markdown
+//! <!-- kree:start -d 2 --compact -->
+//! ```text
+//! ...generated tree...
+//! ```
+//! <!-- kree:end -->
+//! ````
+//!
+//! Rendering ignores `~/.kreerc` so the output is the same on every machine.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+
+use crate::ignore::IgnoreFilter;
+use crate::notes::{Notes, annotate_tree};
+use crate::render::{RenderOptions, build_color_map, format_tree};
+use crate::style::Styler;
+use crate::theme::UiTheme;
+use crate::tree::{SortMode, TreeOptions, compact_tree, default_depth, load_tree};
+
+const START_MARKER: &str = "<!-- kree:start";
+const END_MARKER: &str = "<!-- kree:end -->";
+
+/// Options accepted inside a start marker.
+#[derive(Parser)]
+#[command(name = "kree:start", no_binary_name = true, disable_help_flag = true)]
+struct MarkerArgs {
+    /// Root directory, relative to the Markdown file.
+    #[arg(default_value = ".")]
+    path: PathBuf,
+
+    #[arg(short, long)]
+    depth: Option<u32>,
+
+    #[arg(short, long)]
+    all: bool,
+
+    #[arg(short, long, value_enum)]
+    sort: Option<SortMode>,
+
+    #[arg(long)]
+    dirs_only: bool,
+
+    #[arg(short = 'e', long, value_delimiter = ',')]
+    extensions: Vec<String>,
+
+    #[arg(long)]
+    detect: bool,
+
+    #[arg(long)]
+    compact: bool,
+
+    #[arg(long)]
+    doc_notes: bool,
+
+    #[arg(long)]
+    no_gitignore: bool,
+}
+
+/// Result of regenerating the blocks of a Markdown file.
+pub struct Update {
+    /// File contents with every block regenerated.
+    pub contents: String,
+    /// 1-based line numbers of the start markers whose block changed.
+    pub stale: Vec<usize>,
+}
+
+/// Regenerates every kree block in `contents`, resolving marker paths
+/// relative to `base_dir`.
+pub fn update_readme(contents: &str, base_dir: &Path) -> Result<Update, String> {
+    update_blocks(contents, |args| render_block(args, base_dir))
+}
+
+/// Replaces the body of each marked block with `render(marker_args)`.
+///
+/// An existing opening fence (e.g. ```` ```text ````) is kept; otherwise a
+/// plain ```` ``` ```` fence is used. Generated lines end like the first
+/// line of `contents` (`\n` or `\r\n`). Markers inside fenced code blocks
+/// are examples and left alone.
+fn update_blocks(
+    contents: &str,
+    render: impl Fn(&str) -> Result<String, String>,
+) -> Result<Update, String> {
+    let lines: Vec<&str> = contents.split_inclusive('\n').collect();
+    let newline = if lines.first().is_some_and(|l| l.ends_with("\r\n")) {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut output = String::with_capacity(contents.len());
+    let mut stale = Vec::new();
+    let mut found = false;
+    let mut open_fence: Option<(char, usize)> = None;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        output.push_str(line);
+        if let Some((marker, len, info)) = parse_fence(line) {
+            match open_fence {
+                None => open_fence = Some((marker, len)),
+                Some((open, open_len)) if marker == open && len >= open_len && info.is_empty() => {
+                    open_fence = None;
+                }
+                Some(_) => {}
+            }
+            i += 1;
+            continue;
+        }
+        let Some(args) = parse_start_marker(line).filter(|_| open_fence.is_none()) else {
+            i += 1;
+            continue;
+        };
+        found = true;
+
+        let start = i + 1;
+        let Some(end) = (start..lines.len()).find(|&j| lines[j].trim() == END_MARKER) else {
+            return Err(format!("line {start}: missing {END_MARKER}"));
+        };
+
+        let old_body: String = lines[start..end].concat();
+        let fence = lines[start..end]
+            .first()
+            .map(|l| l.trim_end())
+            .filter(|l| l.starts_with("```"))
+            .unwrap_or("```");
+        let tree = render(args).map_err(|e| format!("line {start}: {e}"))?;
+        let mut new_body = format!("{fence}\n{tree}```\n");
+        if newline != "\n" {
+            new_body = new_body.replace('\n', newline);
+        }
+
+        if new_body != old_body {
+            stale.push(start);
+        }
+        output.push_str(&new_body);
+        output.push_str(lines[end]);
+        i = end + 1;
+    }
+
+    if !found {
+        return Err(format!("no {START_MARKER} --> marker found"));
+    }
+
+    Ok(Update {
+        contents: output,
+        stale,
+    })
+}
+
+/// Returns the options of a `<!-- kree:start ... -->` line.
+fn parse_start_marker(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix(START_MARKER)?;
+    let args = rest.strip_suffix("-->")?;
+    // Reject e.g. `<!-- kree:started -->`
+    if !args.is_empty() && !args.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(args.trim())
+}
+
+/// Returns the marker character, length and info string of a code fence
+/// line (```` ```text ```` or `~~~`), indented by at most three spaces.
+fn parse_fence(line: &str) -> Option<(char, usize, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let marker = trimmed.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let len = trimmed.len() - trimmed.trim_start_matches(marker).len();
+    if len < 3 {
+        return None;
+    }
+    let info = trimmed[len..].trim();
+    // A backtick fence's info string cannot contain backticks
+    if marker == '`' && info.contains('`') {
+        return None;
+    }
+    Some((marker, len, info))
+}
+
+/// Renders the uncolored tree for one marker.
+fn render_block(args: &str, base_dir: &Path) -> Result<String, String> {
+    let args = MarkerArgs::try_parse_from(args.split_whitespace())
+        .map_err(|e| e.to_string().lines().next().unwrap_or_default().to_string())?;
+
+    let root_path = base_dir.join(&args.path);
+    if !root_path.is_dir() {
+        return Err(format!("'{}' is not a directory", root_path.display()));
+    }
+
+    let depth = args.depth.unwrap_or_else(|| default_depth(&root_path));
+    let opts = TreeOptions {
+        dirs_only: args.dirs_only,
+        extensions: args
+            .extensions
+            .iter()
+            .map(|e| e.trim_start_matches('.').to_lowercase())
+            .collect(),
+        show_metadata: false,
+        detect_kinds: args.detect,
+        compact: args.compact,
+        hash: None,
+        loc: false,
+    };
+    let filter = IgnoreFilter::with_gitignore(!args.all, &[], !args.no_gitignore, &root_path);
+    let sort = args.sort.unwrap_or(SortMode::Kind);
+
+    let mut root = load_tree(&root_path, depth, 0, &filter, sort, &opts);
+    annotate_tree(&mut root, &Notes::load(&root_path, args.doc_notes));
+    if opts.compact {
+        compact_tree(&mut root);
+    }
+    // Show the root as written in the marker, not as an absolute path
+    root.name = args.path.display().to_string();
+
+    let styler = Styler::new(
+        build_color_map(&HashMap::new()),
+        None,
+        Vec::new(),
+        UiTheme::default(),
+    );
+    let opts = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    Ok(format_tree(&root, &styler, None, &opts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_marker_options() {
+        assert_eq!(
+            parse_start_marker("<!-- kree:start -d 2 -->\n"),
+            Some("-d 2")
+        );
+        assert_eq!(parse_start_marker("<!-- kree:start -->"), Some(""));
+        assert_eq!(parse_start_marker("<!-- kree:started -->"), None);
+        assert_eq!(parse_start_marker("text"), None);
+    }
+
+    #[test]
+    fn blocks_are_replaced_and_fence_kept() {
+        let readme =
+            "# Title\n<!-- kree:start -d 1 -->\n```text\nold\n```\n<!-- kree:end -->\nafter\n";
+        let update = update_blocks(readme, |args| Ok(format!("tree {args}\n"))).unwrap();
+        assert_eq!(
+            update.contents,
+            "# Title\n<!-- kree:start -d 1 -->\n```text\ntree -d 1\n```\n<!-- kree:end -->\nafter\n"
+        );
+        assert_eq!(update.stale, vec![2]);
+
+        let again = update_blocks(&update.contents, |args| Ok(format!("tree {args}\n"))).unwrap();
+        assert!(again.stale.is_empty());
+        assert_eq!(again.contents, update.contents);
+    }
+
+    #[test]
+    fn crlf_line_endings_are_kept() {
+        let readme = "<!-- kree:start -->\r\n```\r\na\r\nb\r\n```\r\n<!-- kree:end -->\r\n";
+        let update = update_blocks(readme, |_| Ok("a\nb\n".to_string())).unwrap();
+        assert!(update.stale.is_empty());
+        assert_eq!(update.contents, readme);
+    }
+
+    #[test]
+    fn markers_in_fenced_examples_are_skipped() {
+        let readme = "````markdown\n<!-- kree:start -->\n```text\nexample\n```\n<!-- kree:end -->\n````\n\
+                      <!-- kree:start -->\n```\nold\n```\n<!-- kree:end -->\n";
+        let update = update_blocks(readme, |_| Ok("new\n".to_string())).unwrap();
+        assert_eq!(update.stale, vec![8]);
+        assert_eq!(update.contents, readme.replace("old", "new"));
+
+        let only_example = "~~~\n<!-- kree:start -->\n<!-- kree:end -->\n~~~\n";
+        assert!(update_blocks(only_example, |_| Ok(String::new())).is_err());
+    }
+
+    #[test]
+    fn missing_markers_are_errors() {
+        let render = |_: &str| Ok(String::new());
+        assert!(update_blocks("no markers\n", render).is_err());
+        assert!(update_blocks("<!-- kree:start -->\n```\n```\n", render).is_err());
+    }
+
+    #[test]
+    fn invalid_marker_options() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(render_block("--bogus", dir.path()).is_err());
+        assert!(render_block("missing-dir", dir.path()).is_err());
+    }
+
+    #[test]
+    fn renders_uncolored_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        let tree = render_block("-d 2", dir.path()).unwrap();
+        assert_eq!(
+            tree,
+            "└── .\n     └── src\n          └── main.rs\n\n1 directories, 1 files\n"
+        );
+    }
+}
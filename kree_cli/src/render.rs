@@ -1,10 +1,36 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
-use colored::{ColoredString, Colorize};
-
-use crate::tree::TreeNode;
+use unicode_width::UnicodeWidthChar;
+
+use crate::filetype::FileKind;
+use crate::heatmap::Heatmap;
+use crate::icons::IconSet;
+use crate::loc::{format_language_summary, format_loc};
+use crate::style::{Styler, TextStyle};
+use crate::term::hyperlink;
+use crate::tree::{NodeMetadata, TreeNode};
+
+/// Options controlling how the tree is rendered to the terminal.
+#[derive(Clone, Default)]
+pub struct RenderOptions {
+    /// Wrap each name in an OSC 8 `file://` hyperlink.
+    pub hyperlinks: bool,
+    /// Print the metadata columns collected by `--long`.
+    pub show_metadata: bool,
+    /// Color names by a gradient instead of by extension (`--color-by`).
+    pub heatmap: Option<Heatmap>,
+    /// Labels printed after the names of the entries at these paths, such
+    /// as the `[dup #3]` of `--dupes`.
+    pub tags: HashMap<PathBuf, String>,
+    /// Print the code line counts of `--loc` and the per-language summary.
+    pub show_loc: bool,
+    /// Write no ANSI styles, whatever the color settings (Markdown export,
+    /// README blocks).
+    pub plain: bool,
+}
 
 /// Map of file extensions to RGB color tuples.
 pub type ColorMap = HashMap<String, (u8, u8, u8)>;
@@ -16,7 +42,7 @@ pub type IconMap = HashMap<String, String>;
 /// Supports:
 /// - Hex codes: `#RRGGBB`
 /// - Standard color names: `red`, `blue`, `bright_green`, etc.
-fn parse_color(name: &str) -> Option<(u8, u8, u8)> {
+pub(crate) fn parse_color(name: &str) -> Option<(u8, u8, u8)> {
     if let Some(hex) = name.strip_prefix('#') {
         if hex.len() == 6 {
             let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
@@ -118,89 +144,16 @@ pub fn build_color_map(user_colors: &HashMap<String, String>) -> ColorMap {
     map
 }
 
-/// Builds an `IconMap` by merging default icons with user-provided overrides.
-///
-/// Icons are Nerd Font characters. Defaults cover common file types.
-pub fn build_icon_map(user_icons: &HashMap<String, String>) -> IconMap {
-    let defaults: &[(&str, &str)] = &[
-        // Special
-        ("directory", "\u{f115}"),  //
-        ("executable", "\u{f489}"), //
-        ("default", "\u{f15b}"),    //
-        // Languages
-        ("rs", "\u{e7a8}"),    //
-        ("py", "\u{e73c}"),    //
-        ("js", "\u{e74e}"),    //
-        ("ts", "\u{e628}"),    //
-        ("jsx", "\u{e7ba}"),   //
-        ("tsx", "\u{e7ba}"),   //
-        ("go", "\u{e626}"),    //
-        ("rb", "\u{e739}"),    //
-        ("java", "\u{e738}"),  //
-        ("c", "\u{e61e}"),     //
-        ("cpp", "\u{e61d}"),   //
-        ("h", "\u{e61e}"),     //
-        ("hpp", "\u{e61d}"),   //
-        ("lua", "\u{e620}"),   //
-        ("php", "\u{e73d}"),   //
-        ("swift", "\u{e755}"), //
-        ("kt", "\u{e634}"),    //
-        ("dart", "\u{e798}"),  //
-        ("zig", "\u{e6a9}"),   //
-        ("ex", "\u{e62d}"),    //
-        ("hs", "\u{e61f}"),    //
-        ("sh", "\u{f489}"),    //
-        ("bash", "\u{f489}"),  //
-        ("zsh", "\u{f489}"),   //
-        ("cs", "\u{f81a}"),    //
-        ("r", "\u{f25d}"),     //
-        // Web
-        ("html", "\u{e736}"), //
-        ("css", "\u{e749}"),  //
-        ("scss", "\u{e749}"), //
-        ("vue", "\u{e6a0}"),  //
-        // Data / Config
-        ("json", "\u{e60b}"), //
-        ("toml", "\u{e60b}"), //
-        ("yaml", "\u{e60b}"), //
-        ("yml", "\u{e60b}"),  //
-        ("xml", "\u{e619}"),  //
-        ("csv", "\u{f1c3}"),  //
-        // Docs
-        ("md", "\u{e73e}"),  //
-        ("txt", "\u{f15c}"), //
-        ("rst", "\u{f15c}"), //
-        ("pdf", "\u{f1c1}"), //
-        // Images
-        ("png", "\u{f1c5}"),  //
-        ("jpg", "\u{f1c5}"),  //
-        ("jpeg", "\u{f1c5}"), //
-        ("gif", "\u{f1c5}"),  //
-        ("svg", "\u{f1c5}"),  //
-        ("ico", "\u{f1c5}"),  //
-        ("bmp", "\u{f1c5}"),  //
-        ("webp", "\u{f1c5}"), //
-        // Archives
-        ("zip", "\u{f1c6}"), //
-        ("tar", "\u{f1c6}"), //
-        ("gz", "\u{f1c6}"),  //
-        ("bz2", "\u{f1c6}"), //
-        ("xz", "\u{f1c6}"),  //
-        ("rar", "\u{f1c6}"), //
-        ("7z", "\u{f1c6}"),  //
-        // Other
-        ("lock", "\u{f023}"),       //
-        ("dockerfile", "\u{e7b0}"), //
-        ("gitignore", "\u{e702}"),  //
-    ];
-
+/// Builds an `IconMap` by merging the defaults of `set` with user-provided
+/// overrides.
+pub fn build_icon_map(user_icons: &HashMap<String, String>, set: IconSet) -> IconMap {
     let mut map = IconMap::new();
-    for &(key, icon) in defaults {
+    for &(key, icon) in set.defaults() {
         map.insert(key.to_string(), icon.to_string());
     }
 
     for (key, icon) in user_icons {
-        map.insert(key.clone(), icon.clone());
+        map.insert(key.to_lowercase(), icon.clone());
     }
 
     map
@@ -208,36 +161,58 @@ pub fn build_icon_map(user_icons: &HashMap<String, String>) -> IconMap {
 
 /// Determines the icon to use for a given file path.
 ///
-/// Priority:
-/// 1. Directory icon (if it's a directory)
-/// 2. Exact extension match
-/// 3. Exact filename match (e.g. `Dockerfile`)
-/// 4. Executable icon (if executable)
+/// Directories use, in order, a `dir:<name>` icon, `directory_open` when
+/// `open` is set, and `directory`. Files use:
+/// 1. Exact filename match (e.g. `Cargo.toml`, `Makefile`)
+/// 2. Detected kind's canonical extension
+/// 3. Exact extension match
+/// 4. Executable icon (if executable; only checked when `disk` is set, as
+///    entries of `--fromfile` and `--load` trees are not on the disk)
 /// 5. Default icon
-pub(crate) fn icon_for_node<'a>(path: &Path, icon_map: &'a IconMap) -> &'a str {
-    if path.is_dir() {
-        if let Some(icon) = icon_map.get("directory") {
-            return icon.as_str();
-        }
-        return "";
+pub(crate) fn icon_for_node<'a>(
+    path: &Path,
+    is_dir: bool,
+    kind: Option<FileKind>,
+    icon_map: &'a IconMap,
+    open: bool,
+    disk: bool,
+) -> &'a str {
+    let filename = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(str::to_lowercase);
+
+    if is_dir {
+        let named = filename.and_then(|name| icon_map.get(&format!("dir:{name}")));
+        let open_icon = if open {
+            icon_map.get("directory_open")
+        } else {
+            None
+        };
+        return named
+            .or(open_icon)
+            .or_else(|| icon_map.get("directory"))
+            .map_or("", String::as_str);
     }
 
-    // Try extension first
-    if let Some(ext) = path.extension().and_then(|e| e.to_str())
-        && let Some(icon) = icon_map.get(&ext.to_lowercase())
-    {
+    // Try full filename first (e.g. Cargo.toml, Dockerfile, .gitignore)
+    if let Some(icon) = filename.and_then(|name| icon_map.get(&name)) {
+        return icon.as_str();
+    }
+
+    if let Some(icon) = kind.and_then(|k| icon_map.get(k.ext)) {
         return icon.as_str();
     }
 
-    // Try full filename (e.g. Dockerfile, .gitignore)
-    if let Some(filename) = path.file_name().and_then(|n| n.to_str())
-        && let Some(icon) = icon_map.get(&filename.to_lowercase())
+    if let Some(ext) = path.extension().and_then(|e| e.to_str())
+        && let Some(icon) = icon_map.get(&ext.to_lowercase())
     {
         return icon.as_str();
     }
 
     // Executable check
-    if is_executable(path)
+    if disk
+        && is_executable(path)
         && let Some(icon) = icon_map.get("executable")
     {
         return icon.as_str();
@@ -252,48 +227,93 @@ pub(crate) fn icon_for_node<'a>(path: &Path, icon_map: &'a IconMap) -> &'a str {
 }
 
 fn colorize_name(
-    name: &str,
-    path: &Path,
-    color_map: &ColorMap,
+    node: &TreeNode,
+    depth: usize,
+    styler: &Styler,
     icon_map: Option<&IconMap>,
+    opts: &RenderOptions,
 ) -> String {
-    let colored = if path.is_dir() {
-        name.blue().bold().to_string()
-    } else if is_executable(path) {
-        name.green().bold().to_string()
-    } else {
-        colorize_by_extension(name, path, color_map).to_string()
-    };
+    let (name, path) = (node.name.as_str(), node.path.as_path());
+    let mut style = styler.style_for(path, node.is_dir, node.is_symlink, node.file_kind);
+    if let Some(color) = opts.heatmap.as_ref().and_then(|h| h.color_for(node, depth)) {
+        style.fg = Some(color);
+    }
+    let mut colored = paint(style, name, opts);
+
+    if opts.hyperlinks {
+        colored = hyperlink(&colored, path, None);
+    }
 
     match icon_map {
         Some(im) => {
-            let icon = icon_for_node(path, im);
+            let icon = icon_for_node(
+                path,
+                node.is_dir,
+                node.file_kind,
+                im,
+                false,
+                styler.disk_lookups(),
+            );
             format!("{icon} {colored}")
         }
         None => colored,
     }
 }
 
-/// Returns a cyan-colored `" -> target"` suffix for symlinks, or empty string.
-fn symlink_suffix(node: &TreeNode) -> String {
+/// Returns a `" -> target"` suffix in the theme's symlink color for symlinks,
+/// or empty string.
+fn symlink_suffix(node: &TreeNode, styler: &Styler, opts: &RenderOptions) -> String {
     if node.is_symlink {
         let target = node
             .symlink_target
             .as_ref()
             .map(|p| p.display().to_string())
             .unwrap_or_else(|| "?".to_string());
-        format!(" {}", format!("-> {target}").cyan())
+        let style = TextStyle::fg(styler.ui().symlink);
+        format!(" {}", paint(style, &format!("-> {target}"), opts))
     } else {
         String::new()
     }
 }
 
-fn colorize_by_extension(name: &str, path: &Path, color_map: &ColorMap) -> ColoredString {
-    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+/// Formats the tag of a node, if `opts` has one for its path.
+fn tag_suffix(node: &TreeNode, styler: &Styler, opts: &RenderOptions) -> String {
+    match opts.tags.get(&node.path) {
+        Some(tag) => format!(
+            " {}",
+            paint(TextStyle::fg(styler.ui().highlight), tag, opts)
+        ),
+        None => String::new(),
+    }
+}
+
+/// Formats the code line count of a node as a column, with `--loc`.
+fn loc_suffix(node: &TreeNode, styler: &Styler, opts: &RenderOptions) -> String {
+    match node.loc {
+        Some(counts) if opts.show_loc => {
+            format!(
+                "  {}",
+                paint(styler.ui().metadata, &format_loc(&counts), opts)
+            )
+        }
+        _ => String::new(),
+    }
+}
+
+/// Paints tree-drawing characters in the theme's tree-line color, if any.
+fn tree_lines(lines: &str, styler: &Styler, opts: &RenderOptions) -> String {
+    match styler.ui().tree_lines {
+        Some(color) => paint(TextStyle::fg(color), lines, opts),
+        None => lines.to_string(),
+    }
+}
 
-    match color_map.get(&ext.to_lowercase()) {
-        Some(&(r, g, b)) => name.truecolor(r, g, b),
-        None => name.bright_white(),
+/// Applies `style` to `text`, unless `opts` asks for plain output.
+fn paint(style: TextStyle, text: &str, opts: &RenderOptions) -> String {
+    if opts.plain {
+        text.to_string()
+    } else {
+        style.paint(text).to_string()
     }
 }
 
@@ -321,8 +341,9 @@ fn count_entries(node: &TreeNode) -> (usize, usize) {
     let mut dirs = 0usize;
     let mut files = 0usize;
     for child in &node.children {
-        if child.path.is_dir() {
-            dirs += 1;
+        if child.is_dir {
+            // A compacted chain stands for every directory merged into it
+            dirs += child.merged.len().max(1);
         } else {
             files += 1;
         }
@@ -334,7 +355,7 @@ fn count_entries(node: &TreeNode) -> (usize, usize) {
 }
 
 /// Formats a file size in human-readable form.
-fn format_size(size: u64) -> String {
+pub(crate) fn format_size(size: u64) -> String {
     const KIB: u64 = 1024;
     const MIB: u64 = 1024 * 1024;
     const GIB: u64 = 1024 * 1024 * 1024;
@@ -361,7 +382,7 @@ fn format_mode(mode: u32) -> String {
 }
 
 /// Formats a SystemTime as a short date string.
-fn format_time(time: &SystemTime) -> String {
+pub(crate) fn format_time(time: &SystemTime) -> String {
     let duration = time
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap_or_default();
@@ -394,11 +415,25 @@ fn days_to_ymd(mut days: i64) -> (i64, i64, i64) {
 }
 
 /// Formats the metadata suffix for a tree node.
-fn metadata_suffix(node: &TreeNode) -> String {
+fn metadata_suffix(node: &TreeNode, styler: &Styler, opts: &RenderOptions) -> String {
     let Some(ref meta) = node.metadata else {
         return String::new();
     };
+    if !opts.show_metadata {
+        return String::new();
+    }
+
+    let parts = metadata_columns(meta);
+    if parts.is_empty() {
+        return String::new();
+    }
+
+    format!("  {}", paint(styler.ui().metadata, &parts.join("  "), opts))
+}
 
+/// Formats the `--long` columns that are present: permissions, owner, size
+/// (right-aligned) and modification date.
+pub(crate) fn metadata_columns(meta: &NodeMetadata) -> Vec<String> {
     let mut parts = Vec::new();
 
     #[cfg(unix)]
@@ -419,64 +454,156 @@ fn metadata_suffix(node: &TreeNode) -> String {
         parts.push(format_time(modified));
     }
 
-    if parts.is_empty() {
-        return String::new();
-    }
-
-    format!("  {}", parts.join("  ").dimmed())
+    parts
 }
 
 /// Renders the directory tree to stdout.
 ///
+/// Lines are written as they are rendered, except for trees with notes,
+/// whose note column is only known once every line has been rendered.
+///
 /// # Arguments
 ///
 /// * `root` - The root node of the tree.
-/// * `color_map` - Configuration for file colors.
+/// * `styler` - Resolves file colors and text attributes.
 /// * `icon_map` - Optional configuration for file icons.
-pub fn render_tree(root: &TreeNode, color_map: &ColorMap, icon_map: Option<&IconMap>) {
-    println!(
-        "└── {}{}{}",
-        colorize_name(&root.name, &root.path, color_map, icon_map),
-        symlink_suffix(root),
-        metadata_suffix(root)
+/// * `opts` - Rendering options (hyperlinks, etc.).
+pub fn render_tree(
+    root: &TreeNode,
+    styler: &Styler,
+    icon_map: Option<&IconMap>,
+    opts: &RenderOptions,
+) {
+    // A closed pipe (`kree | head`) just ends the output
+    let _ = write_tree(root, styler, icon_map, opts, &mut io::stdout().lock());
+}
+
+/// Renders the directory tree, including the summary line, into a string.
+///
+/// Notes are printed as `# comments` aligned in a single column after the
+/// longest annotated line.
+pub fn format_tree(
+    root: &TreeNode,
+    styler: &Styler,
+    icon_map: Option<&IconMap>,
+    opts: &RenderOptions,
+) -> String {
+    let mut output = Vec::new();
+    write_tree(root, styler, icon_map, opts, &mut output).expect("writing to a Vec cannot fail");
+    String::from_utf8(output).expect("rendered lines are UTF-8")
+}
+
+/// Writes the tree and its summary line to `out`, as `format_tree`
+/// describes.
+fn write_tree(
+    root: &TreeNode,
+    styler: &Styler,
+    icon_map: Option<&IconMap>,
+    opts: &RenderOptions,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let align_notes = has_notes(root);
+    let mut lines = Vec::new();
+    let mut result = Ok(());
+    let mut emit = |line: String, note: Option<&str>| {
+        if align_notes {
+            lines.push((line, note.map(str::to_string)));
+        } else if result.is_ok() {
+            result = writeln!(out, "{line}");
+        }
+    };
+
+    emit(
+        format!(
+            "{}{}{}{}{}{}",
+            tree_lines("└── ", styler, opts),
+            colorize_name(root, 0, styler, icon_map, opts),
+            symlink_suffix(root, styler, opts),
+            tag_suffix(root, styler, opts),
+            metadata_suffix(root, styler, opts),
+            loc_suffix(root, styler, opts)
+        ),
+        root.note.as_deref(),
     );
     let child_count = root.children.len();
     for (i, child) in root.children.iter().enumerate() {
         let is_last = i == child_count - 1;
         let mask = if is_last { 0b11u64 } else { 0b01u64 };
-        render_node(child, 1, is_last, mask, color_map, icon_map);
+        render_node(child, 1, is_last, mask, styler, icon_map, opts, &mut emit);
+    }
+    result?;
+
+    let note_column = lines
+        .iter()
+        .filter(|(_, note)| note.is_some())
+        .map(|(line, _)| visible_width(line))
+        .max()
+        .unwrap_or(0);
+    for (line, note) in &lines {
+        match note {
+            Some(note) => {
+                let padding = note_column - visible_width(line) + 2;
+                let note = paint(styler.ui().metadata, &format!("# {note}"), opts);
+                writeln!(out, "{line}{}{note}", " ".repeat(padding))?;
+            }
+            None => writeln!(out, "{line}")?,
+        }
     }
+
     let (dirs, files) = count_entries(root);
-    println!("\n{dirs} directories, {files} files");
+    write!(out, "\n{dirs} directories, {files} files\n")?;
+    if opts.show_loc {
+        let summary = format_language_summary(root);
+        if let Some((header, rows)) = summary.split_once('\n') {
+            let header = paint(TextStyle::default().bold(), header, opts);
+            write!(out, "\n{header}\n{rows}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if any entry of the tree has a note.
+fn has_notes(node: &TreeNode) -> bool {
+    node.note.is_some() || node.children.iter().any(has_notes)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn render_node(
     node: &TreeNode,
     depth: u32,
     is_last: bool,
     mask: u64,
-    color_map: &ColorMap,
+    styler: &Styler,
     icon_map: Option<&IconMap>,
+    opts: &RenderOptions,
+    emit: &mut dyn FnMut(String, Option<&str>),
 ) {
+    let mut prefix = String::new();
     for i in 0..depth {
         if ((mask >> i) & 1) == 0 {
-            print!("│    ");
+            prefix.push_str("│    ");
         } else {
-            print!("     ");
+            prefix.push_str("     ");
         }
     }
 
     if is_last {
-        print!("└── ");
+        prefix.push_str("└── ");
     } else {
-        print!("├── ");
-    }
-
-    println!(
-        "{}{}{}",
-        colorize_name(&node.name, &node.path, color_map, icon_map),
-        symlink_suffix(node),
-        metadata_suffix(node)
+        prefix.push_str("├── ");
+    }
+
+    emit(
+        format!(
+            "{}{}{}{}{}{}",
+            tree_lines(&prefix, styler, opts),
+            colorize_name(node, depth as usize, styler, icon_map, opts),
+            symlink_suffix(node, styler, opts),
+            tag_suffix(node, styler, opts),
+            metadata_suffix(node, styler, opts),
+            loc_suffix(node, styler, opts)
+        ),
+        node.note.as_deref(),
     );
 
     let child_count = node.children.len();
@@ -492,15 +619,56 @@ fn render_node(
             depth + 1,
             child_is_last,
             new_mask,
-            color_map,
+            styler,
             icon_map,
+            opts,
+            emit,
         );
     }
 }
 
+/// Returns the number of terminal columns `s` takes, skipping ANSI color
+/// (CSI) and OSC 8 hyperlink escape sequences. Wide characters such as CJK
+/// and most emoji take two columns.
+fn visible_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            width += c.width().unwrap_or(0);
+            continue;
+        }
+        match chars.next() {
+            // CSI: ends with a byte in '@'..='~'
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: ends with BEL or ESC '\'
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    width
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
 
     // parse_color tests
 
@@ -549,7 +717,7 @@ mod tests {
 
     #[test]
     fn icon_map_has_defaults() {
-        let map = build_icon_map(&HashMap::new());
+        let map = build_icon_map(&HashMap::new(), IconSet::Nerd);
         assert!(map.contains_key("rs"));
     }
 
@@ -557,10 +725,147 @@ mod tests {
     fn icon_map_user_override() {
         let mut user = HashMap::new();
         user.insert("rs".to_string(), "X".to_string());
-        let map = build_icon_map(&user);
+        let map = build_icon_map(&user, IconSet::Nerd);
         assert_eq!(map.get("rs"), Some(&"X".to_string()));
     }
 
+    #[test]
+    fn icon_map_uses_selected_set() {
+        let map = build_icon_map(&HashMap::new(), IconSet::Emoji);
+        assert_eq!(map.get("directory").map(String::as_str), Some("📁"));
+    }
+
+    // icon_for_node tests
+
+    #[test]
+    fn special_filename_beats_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        std::fs::write(&manifest, "").unwrap();
+        let mut user = HashMap::new();
+        user.insert("Cargo.toml".to_string(), "C".to_string());
+        user.insert("toml".to_string(), "T".to_string());
+        let map = build_icon_map(&user, IconSet::Ascii);
+        assert_eq!(
+            icon_for_node(&manifest, false, None, &map, false, true),
+            "C"
+        );
+    }
+
+    #[test]
+    fn folder_icons() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        let other = dir.path().join("other");
+        std::fs::create_dir(&src).unwrap();
+        std::fs::create_dir(&other).unwrap();
+        let map = build_icon_map(&HashMap::new(), IconSet::Emoji);
+        assert_eq!(icon_for_node(&src, true, None, &map, true, true), "🧩");
+        assert_eq!(icon_for_node(&other, true, None, &map, false, true), "📁");
+        assert_eq!(icon_for_node(&other, true, None, &map, true, true), "📂");
+    }
+
+    // format_tree tests
+
+    #[test]
+    fn visible_width_skips_escapes() {
+        let colored = "\x1b[1;34msrc\x1b[0m";
+        let linked = "\x1b]8;;file:///tmp/a\x1b\\a.rs\x1b]8;;\x1b\\";
+        assert_eq!(visible_width(colored), 3);
+        assert_eq!(visible_width(linked), 4);
+        assert_eq!(visible_width("├── main.rs"), 11);
+        // Wide characters take two columns
+        assert_eq!(visible_width("├── 説明.md"), 11);
+        assert_eq!(visible_width("🦀 Cargo.toml"), 13);
+    }
+
+    #[test]
+    fn notes_are_aligned() {
+        let root = TreeNode {
+            name: "root".to_string(),
+            path: PathBuf::from("/nonexistent/root"),
+            children: vec![
+                TreeNode {
+                    name: "a".to_string(),
+                    path: PathBuf::from("/nonexistent/root/a"),
+                    note: Some("short name".to_string()),
+                    ..TreeNode::default()
+                },
+                TreeNode {
+                    name: "longer.rs".to_string(),
+                    path: PathBuf::from("/nonexistent/root/longer.rs"),
+                    note: Some("long name".to_string()),
+                    ..TreeNode::default()
+                },
+            ],
+            ..TreeNode::default()
+        };
+        let styler = crate::style::Styler::new(
+            build_color_map(&HashMap::new()),
+            None,
+            Vec::new(),
+            crate::theme::UiTheme::default(),
+        );
+        let output = format_tree(&root, &styler, None, &RenderOptions::default());
+        let lines: Vec<&str> = output.lines().collect();
+        let note_column = |line: &str| visible_width(&line[..line.find("# ").unwrap()]);
+        assert!(lines[1].contains("# short name"));
+        assert!(lines[2].contains("# long name"));
+        assert_eq!(note_column(lines[1]), note_column(lines[2]));
+        assert_eq!(
+            note_column(lines[2]),
+            "     └── longer.rs  ".chars().count()
+        );
+        assert_eq!(lines[4], "0 directories, 2 files");
+    }
+
+    #[test]
+    fn tags_follow_names() {
+        let root = TreeNode {
+            name: "root".to_string(),
+            path: PathBuf::from("/nonexistent/root"),
+            children: vec![TreeNode {
+                name: "a.bin".to_string(),
+                path: PathBuf::from("/nonexistent/root/a.bin"),
+                ..TreeNode::default()
+            }],
+            ..TreeNode::default()
+        };
+        let styler = crate::style::Styler::new(
+            build_color_map(&HashMap::new()),
+            None,
+            Vec::new(),
+            crate::theme::UiTheme::default(),
+        );
+        let opts = RenderOptions {
+            tags: HashMap::from([(root.children[0].path.clone(), "[dup #1]".to_string())]),
+            plain: true,
+            ..RenderOptions::default()
+        };
+        let output = format_tree(&root, &styler, None, &opts);
+        assert!(output.contains("└── a.bin [dup #1]\n"));
+        assert!(output.starts_with("└── root\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn executable_icon_needs_disk_lookups() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("run");
+        std::fs::write(&file_path, "#!/bin/sh").unwrap();
+        std::fs::set_permissions(&file_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let map = build_icon_map(&HashMap::new(), IconSet::Ascii);
+        assert_eq!(
+            icon_for_node(&file_path, false, None, &map, false, true),
+            "*"
+        );
+        assert_eq!(
+            icon_for_node(&file_path, false, None, &map, false, false),
+            "."
+        );
+    }
+
     // is_executable tests (unix only)
 
     #[cfg(unix)]
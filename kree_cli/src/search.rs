@@ -4,6 +4,9 @@ use std::path::Path;
 
 use colored::Colorize;
 
+use crate::filetype;
+use crate::term::hyperlink;
+
 /// Represents a match found during fuzzy search.
 pub struct SearchResult {
     /// Name of the matched file or directory.
@@ -147,7 +150,7 @@ pub fn content_search(root: &Path, query: &str, max_depth: u32) -> Vec<ContentMa
     results
 }
 
-const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024; // 10 MB
+pub(crate) const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024; // 10 MB
 
 fn content_search_recursive(
     path: &Path,
@@ -190,7 +193,11 @@ fn content_search_recursive(
         Err(_) => return,
     };
 
-    let reader = BufReader::new(file);
+    let mut reader = BufReader::new(file);
+    match reader.fill_buf() {
+        Ok(head) if !filetype::is_binary(head) => {}
+        _ => return,
+    }
     let path_str = path.display().to_string();
     let query_lower = query.to_lowercase();
 
@@ -210,18 +217,22 @@ fn content_search_recursive(
 }
 
 /// Prints content search results in grep-like format.
-pub fn print_content_results(results: &[ContentMatch]) {
+///
+/// When `hyperlinks` is true, each `path:line` is wrapped in an OSC 8 link
+/// that opens the file at the matched line.
+pub fn print_content_results(results: &[ContentMatch], hyperlinks: bool) {
     if results.is_empty() {
         println!("No matches found");
         return;
     }
     for m in results {
-        println!(
-            "{}:{}:{}",
-            m.path.magenta(),
-            m.line_number.to_string().green(),
-            m.line_content
-        );
+        let location = format!("{}:{}", m.path.magenta(), m.line_number.to_string().green());
+        let location = if hyperlinks {
+            hyperlink(&location, Path::new(&m.path), Some(m.line_number))
+        } else {
+            location
+        };
+        println!("{}:{}", location, m.line_content);
     }
     println!("\n{} matches", results.len());
 }
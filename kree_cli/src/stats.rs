@@ -0,0 +1,478 @@
+//! Tree statistics (`--stats`).
+//!
+//! Summarizes a tree instead of drawing it: entry counts by type, extension
+//! and kind, sizes, the largest and the newest and oldest files, how entries
+//! spread over depths, and empty directories. Directory and file counts
+//! follow the footer of the tree view, where a `--compact` chain counts as
+//! every directory merged into it.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::export::format_iso_time;
+use crate::filetype;
+use crate::render::{format_size, format_time};
+use crate::tree::TreeNode;
+
+/// Width of the longest bar of the depth histogram.
+const BAR_WIDTH: usize = 30;
+
+/// Statistics of a tree, excluding its root.
+#[derive(Debug, Default, Serialize)]
+pub struct TreeStats {
+    pub directories: usize,
+    /// Files, including symlinks to files.
+    pub files: usize,
+    /// Symlinks, to files or directories.
+    pub symlinks: usize,
+    /// Sum of the file sizes; symlinks are not followed.
+    pub total_size: u64,
+    /// Mean size of the files with a known size.
+    pub average_size: u64,
+    /// Files by lowercase extension, most files first; `""` when there is
+    /// none.
+    pub extensions: Vec<GroupStats>,
+    /// Files by detected kind (or the kind of their extension without
+    /// `--detect`), most files first.
+    pub kinds: Vec<GroupStats>,
+    pub largest_files: Vec<SizedEntry>,
+    /// Directories by the total size of the files below them.
+    pub largest_directories: Vec<SizedEntry>,
+    pub newest_files: Vec<DatedEntry>,
+    pub oldest_files: Vec<DatedEntry>,
+    /// Entries at each depth, from 1 for the root's children.
+    pub depths: Vec<DepthStats>,
+    /// Directories without entries, relative to the root. Directories at the
+    /// depth limit are left out since their contents were not read.
+    pub empty_directories: Vec<String>,
+}
+
+/// Files sharing an extension or kind.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct GroupStats {
+    pub name: String,
+    pub files: usize,
+    pub size: u64,
+}
+
+/// An entry and its size, with its path relative to the root.
+#[derive(Debug, Serialize)]
+pub struct SizedEntry {
+    pub path: String,
+    pub size: u64,
+}
+
+/// A file and its modification time, with its path relative to the root.
+#[derive(Debug, Serialize)]
+pub struct DatedEntry {
+    pub path: String,
+    /// Modification time in UTC, as in exports.
+    pub modified: String,
+    #[serde(skip)]
+    time: SystemTime,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DepthStats {
+    pub depth: usize,
+    pub directories: usize,
+    pub files: usize,
+}
+
+/// Running totals while walking the tree.
+#[derive(Default)]
+struct Collector {
+    stats: TreeStats,
+    sized_files: u64,
+    extensions: HashMap<String, GroupStats>,
+    kinds: HashMap<String, GroupStats>,
+    files: Vec<(String, u64)>,
+    directories: Vec<(String, u64)>,
+    dated: Vec<(String, SystemTime)>,
+}
+
+impl TreeStats {
+    /// Collects the statistics of `root`, scanned to `max_depth`, keeping
+    /// the `top` largest, newest and oldest entries.
+    pub fn collect(root: &TreeNode, max_depth: u32, top: usize) -> Self {
+        let mut collector = Collector::default();
+        collector.walk(root, &root.path, 1, max_depth as usize);
+        collector.finish(top)
+    }
+}
+
+impl Collector {
+    /// Adds the children of `node` and returns the total size of the files
+    /// below it.
+    fn walk(&mut self, node: &TreeNode, root: &Path, depth: usize, max_depth: usize) -> u64 {
+        if self.stats.depths.len() < depth && !node.children.is_empty() {
+            self.stats.depths.push(DepthStats {
+                depth,
+                ..DepthStats::default()
+            });
+        }
+        let mut total = 0;
+        for child in &node.children {
+            let path = relative(&child.path, root);
+            if child.is_symlink {
+                self.stats.symlinks += 1;
+            }
+            if child.is_dir {
+                let count = child.merged.len().max(1);
+                self.stats.directories += count;
+                self.stats.depths[depth - 1].directories += count;
+                if child.is_symlink {
+                    continue;
+                }
+                if child.children.is_empty() && depth < max_depth {
+                    self.stats.empty_directories.push(path.clone());
+                }
+                let size = self.walk(child, root, depth + 1, max_depth);
+                self.directories.push((path, size));
+                total += size;
+            } else {
+                self.stats.files += 1;
+                self.stats.depths[depth - 1].files += 1;
+                if !child.is_symlink {
+                    total += self.add_file(child, path);
+                }
+            }
+        }
+        total
+    }
+
+    /// Adds a regular file and returns its size, or 0 when unknown.
+    fn add_file(&mut self, node: &TreeNode, path: String) -> u64 {
+        let meta = node.metadata.as_ref();
+        let size = meta.and_then(|m| m.size);
+        if let Some(time) = meta.and_then(|m| m.modified) {
+            self.dated.push((path.clone(), time));
+        }
+        if let Some(size) = size {
+            self.sized_files += 1;
+            self.stats.total_size += size;
+            self.files.push((path, size));
+        }
+        let size = size.unwrap_or(0);
+
+        let ext = node
+            .path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        add_to_group(&mut self.extensions, ext, size);
+        if let Some(kind) = node
+            .file_kind
+            .or_else(|| filetype::from_extension(&node.path))
+        {
+            add_to_group(&mut self.kinds, kind.name.to_string(), size);
+        }
+        size
+    }
+
+    fn finish(mut self, top: usize) -> TreeStats {
+        let mut stats = self.stats;
+        stats.average_size = stats.total_size.checked_div(self.sized_files).unwrap_or(0);
+        stats.extensions = sorted_groups(self.extensions);
+        stats.kinds = sorted_groups(self.kinds);
+        stats.largest_files = largest(self.files, top);
+        stats.largest_directories = largest(self.directories, top);
+
+        self.dated
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let dated = |(path, time): &(String, SystemTime)| DatedEntry {
+            path: path.clone(),
+            modified: format_iso_time(time),
+            time: *time,
+        };
+        stats.newest_files = self.dated.iter().take(top).map(dated).collect();
+        stats.oldest_files = self.dated.iter().rev().take(top).map(dated).collect();
+        stats.empty_directories.sort();
+        stats
+    }
+}
+
+fn add_to_group(groups: &mut HashMap<String, GroupStats>, name: String, size: u64) {
+    let group = groups.entry(name.clone()).or_insert(GroupStats {
+        name,
+        files: 0,
+        size: 0,
+    });
+    group.files += 1;
+    group.size += size;
+}
+
+fn sorted_groups(groups: HashMap<String, GroupStats>) -> Vec<GroupStats> {
+    let mut groups: Vec<GroupStats> = groups.into_values().collect();
+    groups.sort_by(|a, b| b.files.cmp(&a.files).then_with(|| a.name.cmp(&b.name)));
+    groups
+}
+
+fn largest(mut entries: Vec<(String, u64)>, top: usize) -> Vec<SizedEntry> {
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    entries
+        .into_iter()
+        .take(top)
+        .map(|(path, size)| SizedEntry { path, size })
+        .collect()
+}
+
+/// Returns `path` relative to `root` with `/` separators.
+fn relative(path: &Path, root: &Path) -> String {
+    let path = path.strip_prefix(root).unwrap_or(path);
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Formats the statistics as a plain-text report. Sections without entries
+/// are left out.
+pub fn format_stats(stats: &TreeStats, color: bool) -> String {
+    let heading = |title: &str| {
+        if color {
+            format!("\n{}\n", title.bold())
+        } else {
+            format!("\n{title}\n")
+        }
+    };
+    let mut out = format!(
+        "{} directories, {} files, {} symlinks\n",
+        stats.directories, stats.files, stats.symlinks
+    );
+    if !stats.largest_files.is_empty() {
+        out.push_str(&format!(
+            "{} total, {} average file size\n",
+            format_size(stats.total_size),
+            format_size(stats.average_size)
+        ));
+    }
+
+    for (title, groups) in [("Extensions", &stats.extensions), ("Kinds", &stats.kinds)] {
+        if groups.is_empty() {
+            continue;
+        }
+        out.push_str(&heading(title));
+        let width = groups
+            .iter()
+            .map(|g| g.name.len().max(6))
+            .max()
+            .unwrap_or(0);
+        for group in groups {
+            let name = if group.name.is_empty() {
+                "(none)"
+            } else {
+                &group.name
+            };
+            out.push_str(&format!(
+                "  {name:<width$}  {:>6}  {:>6}\n",
+                group.files,
+                format_size(group.size)
+            ));
+        }
+    }
+
+    for (title, entries) in [
+        ("Largest files", &stats.largest_files),
+        ("Largest directories", &stats.largest_directories),
+    ] {
+        if entries.is_empty() {
+            continue;
+        }
+        out.push_str(&heading(title));
+        for entry in entries {
+            out.push_str(&format!(
+                "  {:>6}  {}\n",
+                format_size(entry.size),
+                entry.path
+            ));
+        }
+    }
+
+    for (title, entries) in [
+        ("Newest files", &stats.newest_files),
+        ("Oldest files", &stats.oldest_files),
+    ] {
+        if entries.is_empty() {
+            continue;
+        }
+        out.push_str(&heading(title));
+        for entry in entries {
+            out.push_str(&format!("  {}  {}\n", format_time(&entry.time), entry.path));
+        }
+    }
+
+    if !stats.depths.is_empty() {
+        out.push_str(&heading("Depths"));
+        let max = stats
+            .depths
+            .iter()
+            .map(|d| d.directories + d.files)
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        let width = stats.depths.len().to_string().len();
+        for level in &stats.depths {
+            let count = level.directories + level.files;
+            let bar = "█".repeat((count * BAR_WIDTH).div_ceil(max));
+            out.push_str(&format!("  {:>width$}  {bar} {count}\n", level.depth));
+        }
+    }
+
+    if !stats.empty_directories.is_empty() {
+        out.push_str(&heading("Empty directories"));
+        for path in &stats.empty_directories {
+            out.push_str(&format!("  {path}/\n"));
+        }
+    }
+    out
+}
+
+/// Exports the statistics as JSON.
+pub fn stats_json(stats: &TreeStats) -> String {
+    serde_json::to_string_pretty(stats).unwrap_or_else(|e| format!("Error: {e}"))
+}
+
+/// Exports the statistics as YAML.
+pub fn stats_yaml(stats: &TreeStats) -> String {
+    serde_yml::to_string(stats).unwrap_or_else(|e| format!("Error: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ignore::IgnoreFilter;
+    use crate::render::{RenderOptions, build_color_map, format_tree};
+    use crate::style::Styler;
+    use crate::theme::UiTheme;
+    use crate::tree::{SortMode, TreeOptions, compact_tree, load_tree};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn scan(root: &Path, depth: u32) -> TreeNode {
+        let filter = IgnoreFilter::new(false, &[]);
+        let opts = TreeOptions {
+            show_metadata: true,
+            ..TreeOptions::default()
+        };
+        load_tree(
+            &root.to_path_buf(),
+            depth,
+            0,
+            &filter,
+            SortMode::Kind,
+            &opts,
+        )
+    }
+
+    fn fixture() -> tempfile::TempDir {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/util")).unwrap();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        fs::write(root.join("src/main.rs"), "x".repeat(300)).unwrap();
+        fs::write(root.join("src/util/mod.rs"), "x".repeat(100)).unwrap();
+        fs::write(root.join("README.md"), "x".repeat(50)).unwrap();
+        fs::write(root.join("Makefile"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn counts_and_sizes() {
+        let dir = fixture();
+        let stats = TreeStats::collect(&scan(dir.path(), 10), 10, 2);
+        assert_eq!((stats.directories, stats.files, stats.symlinks), (3, 4, 0));
+        assert_eq!(stats.total_size, 450);
+        assert_eq!(stats.average_size, 112);
+        assert_eq!(
+            stats.extensions[0],
+            GroupStats {
+                name: "rs".to_string(),
+                files: 2,
+                size: 400
+            }
+        );
+        assert_eq!(stats.extensions.len(), 3);
+        assert_eq!(stats.kinds[0].name, "rust");
+
+        let largest: Vec<_> = stats
+            .largest_files
+            .iter()
+            .map(|e| e.path.as_str())
+            .collect();
+        assert_eq!(largest, ["src/main.rs", "src/util/mod.rs"]);
+        assert_eq!(stats.largest_directories[0].path, "src");
+        assert_eq!(stats.largest_directories[0].size, 400);
+        assert_eq!(stats.newest_files.len(), 2);
+        assert_eq!(stats.empty_directories, ["empty"]);
+        assert_eq!(
+            stats.depths,
+            [
+                DepthStats {
+                    depth: 1,
+                    directories: 2,
+                    files: 2
+                },
+                DepthStats {
+                    depth: 2,
+                    directories: 1,
+                    files: 1
+                },
+                DepthStats {
+                    depth: 3,
+                    directories: 0,
+                    files: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn depth_limit_hides_unread_directories() {
+        let dir = fixture();
+        let stats = TreeStats::collect(&scan(dir.path(), 1), 1, 10);
+        assert!(stats.empty_directories.is_empty());
+        assert_eq!(stats.depths.len(), 1);
+    }
+
+    #[test]
+    fn counts_match_the_tree_footer() {
+        let dir = fixture();
+        let mut root = scan(dir.path(), 10);
+        compact_tree(&mut root);
+        let stats = TreeStats::collect(&root, 10, 10);
+        let styler = Styler::new(
+            build_color_map(&HashMap::new()),
+            None,
+            Vec::new(),
+            UiTheme::default(),
+        );
+        let tree = format_tree(&root, &styler, None, &RenderOptions::default());
+        let footer = format!("{} directories, {} files", stats.directories, stats.files);
+        assert!(tree.contains(&footer), "{tree}");
+    }
+
+    #[test]
+    fn report_lists_sections() {
+        let dir = fixture();
+        let stats = TreeStats::collect(&scan(dir.path(), 10), 10, 10);
+        let text = format_stats(&stats, false);
+        assert!(text.starts_with("3 directories, 4 files, 0 symlinks\n450B total"));
+        assert!(text.contains("\nExtensions\n  rs"));
+        assert!(text.contains("\n  (none)"));
+        assert!(text.contains("\nEmpty directories\n  empty/\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&stats_json(&stats)).unwrap();
+        assert_eq!(json["total_size"], 450);
+        assert!(
+            json["newest_files"][0]["modified"]
+                .as_str()
+                .unwrap()
+                .ends_with('Z')
+        );
+    }
+}
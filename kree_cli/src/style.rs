@@ -0,0 +1,551 @@
+//! Entry styling.
+//!
+//! Defines the terminal-independent `TextStyle` used for file names and the
+//! `Styler` that decides which style each tree entry gets. Both the tree
+//! renderer and the TUI resolve their colors through a `Styler`, so every
+//! style source (style rules, LS_COLORS, extension colors, built-in defaults)
+//! applies to both outputs.
+
+use std::fs;
+use std::path::Path;
+
+use colored::{ColoredString, Colorize};
+use globset::{Glob, GlobMatcher};
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::config::StyleRuleConfig;
+use crate::filetype::FileKind;
+use crate::lscolors::LsColors;
+use crate::render::{ColorMap, is_executable, parse_color};
+use crate::term::{
+    ColorLevel, ansi256_to_rgb, color_level, colored_color, nearest_ansi16, nearest_ansi256,
+};
+use crate::theme::UiTheme;
+
+/// A color as written in LS_COLORS or in the configuration file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StyleColor {
+    /// Palette index: 0-15 are the basic ANSI colors, 16-255 the xterm extended palette.
+    Indexed(u8),
+    /// 24-bit RGB color.
+    Rgb(u8, u8, u8),
+}
+
+impl StyleColor {
+    /// Converts the color into a `colored` color the terminal supports.
+    fn to_colored(self) -> colored::Color {
+        use colored::Color as C;
+
+        match self {
+            StyleColor::Rgb(r, g, b) => colored_color((r, g, b)),
+            StyleColor::Indexed(index) => {
+                let index = if index >= 16 && color_level() == ColorLevel::Ansi16 {
+                    nearest_ansi16(ansi256_to_rgb(index))
+                } else {
+                    index
+                };
+                match index {
+                    0 => C::Black,
+                    1 => C::Red,
+                    2 => C::Green,
+                    3 => C::Yellow,
+                    4 => C::Blue,
+                    5 => C::Magenta,
+                    6 => C::Cyan,
+                    7 => C::White,
+                    8 => C::BrightBlack,
+                    9 => C::BrightRed,
+                    10 => C::BrightGreen,
+                    11 => C::BrightYellow,
+                    12 => C::BrightBlue,
+                    13 => C::BrightMagenta,
+                    14 => C::BrightCyan,
+                    15 => C::BrightWhite,
+                    n => C::AnsiColor(n),
+                }
+            }
+        }
+    }
+
+    /// Returns the RGB value of the color.
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            StyleColor::Indexed(index) => ansi256_to_rgb(index),
+            StyleColor::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+
+    /// Formats the color as a `#rrggbb` hex code for HTML and diagram exports.
+    pub fn to_hex(self) -> String {
+        let (r, g, b) = self.to_rgb();
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+
+    /// Converts the color into a `ratatui` color the terminal supports.
+    pub fn to_tui(self) -> Color {
+        match (self, color_level()) {
+            (_, ColorLevel::None) => Color::Reset,
+            (StyleColor::Indexed(index), ColorLevel::Ansi16) if index >= 16 => {
+                Color::Indexed(nearest_ansi16(ansi256_to_rgb(index)))
+            }
+            (StyleColor::Indexed(index), _) => Color::Indexed(index),
+            (StyleColor::Rgb(r, g, b), ColorLevel::TrueColor) => Color::Rgb(r, g, b),
+            (StyleColor::Rgb(r, g, b), ColorLevel::Ansi256) => {
+                Color::Indexed(nearest_ansi256((r, g, b)))
+            }
+            (StyleColor::Rgb(r, g, b), ColorLevel::Ansi16) => {
+                Color::Indexed(nearest_ansi16((r, g, b)))
+            }
+        }
+    }
+}
+
+/// Foreground, background and text attributes for an entry name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TextStyle {
+    pub fg: Option<StyleColor>,
+    pub bg: Option<StyleColor>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl TextStyle {
+    /// Creates a style with only a foreground color.
+    pub fn fg(color: StyleColor) -> Self {
+        TextStyle {
+            fg: Some(color),
+            ..TextStyle::default()
+        }
+    }
+
+    /// Returns a copy of the style with bold enabled.
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    /// Applies the style to `text` for terminal output.
+    pub fn paint(&self, text: &str) -> ColoredString {
+        let mut out = ColoredString::from(text);
+        if let Some(fg) = self.fg {
+            out = out.color(fg.to_colored());
+        }
+        if let Some(bg) = self.bg {
+            out = out.on_color(bg.to_colored());
+        }
+        if self.bold {
+            out = out.bold();
+        }
+        if self.dim {
+            out = out.dimmed();
+        }
+        if self.italic {
+            out = out.italic();
+        }
+        if self.underline {
+            out = out.underline();
+        }
+        out
+    }
+
+    /// Converts the style into a `ratatui` style for the TUI.
+    pub fn to_tui(self) -> Style {
+        let mut style = Style::default();
+        if let Some(fg) = self.fg {
+            style = style.fg(fg.to_tui());
+        }
+        if let Some(bg) = self.bg {
+            style = style.bg(bg.to_tui());
+        }
+        if self.bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if self.dim {
+            style = style.add_modifier(Modifier::DIM);
+        }
+        if self.italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        if self.underline {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        style
+    }
+}
+
+/// What a style rule matches against.
+#[derive(Debug)]
+enum RuleMatcher {
+    /// Glob matched against the file name (`*_test.rs`, `Makefile`).
+    Name(GlobMatcher),
+    /// Glob containing `/`, matched against the full path (`src/**/*.rs`).
+    Path(GlobMatcher),
+    /// `dir` or `dir:<glob>`: directories, optionally filtered by name.
+    Dir(Option<GlobMatcher>),
+    /// `executable`: files with an execute permission bit.
+    Executable,
+    /// `symlink`: any symbolic link.
+    Symlink,
+    /// `broken_symlink`: symbolic links whose target does not exist.
+    BrokenSymlink,
+}
+
+/// A compiled style rule.
+#[derive(Debug)]
+pub struct StyleRule {
+    matcher: RuleMatcher,
+    style: TextStyle,
+}
+
+impl StyleRule {
+    /// Checks the rule against an entry. Without `disk`, the executable and
+    /// broken-symlink matchers, which need the file system, never match.
+    fn matches(&self, path: &Path, name: &str, is_dir: bool, is_symlink: bool, disk: bool) -> bool {
+        match &self.matcher {
+            RuleMatcher::Name(glob) => glob.is_match(name),
+            RuleMatcher::Path(glob) => glob.is_match(path),
+            RuleMatcher::Dir(glob) => is_dir && glob.as_ref().is_none_or(|g| g.is_match(name)),
+            RuleMatcher::Executable => disk && is_executable(path),
+            RuleMatcher::Symlink => is_symlink,
+            RuleMatcher::BrokenSymlink => disk && is_symlink && fs::metadata(path).is_err(),
+        }
+    }
+}
+
+/// Compiles `[[styles]]` tables into style rules, keeping their order.
+///
+/// Rules with an invalid pattern or color are skipped with a warning.
+pub fn build_style_rules(configs: &[StyleRuleConfig]) -> Vec<StyleRule> {
+    let mut rules = Vec::new();
+    for config in configs {
+        let Some(matcher) = parse_matcher(&config.pattern) else {
+            eprintln!(
+                "Warning: invalid style pattern '{}' in ~/.kreerc, ignoring",
+                config.pattern
+            );
+            continue;
+        };
+
+        let mut style = TextStyle {
+            bold: config.bold,
+            dim: config.dim,
+            italic: config.italic,
+            underline: config.underline,
+            ..TextStyle::default()
+        };
+        let mut valid = true;
+        for (slot, value) in [(&mut style.fg, &config.fg), (&mut style.bg, &config.bg)] {
+            if let Some(name) = value {
+                match parse_color(name) {
+                    Some((r, g, b)) => *slot = Some(StyleColor::Rgb(r, g, b)),
+                    None => {
+                        eprintln!(
+                            "Warning: unknown color '{name}' for style '{}' in ~/.kreerc, ignoring",
+                            config.pattern
+                        );
+                        valid = false;
+                    }
+                }
+            }
+        }
+
+        if valid {
+            rules.push(StyleRule { matcher, style });
+        }
+    }
+    rules
+}
+
+fn parse_matcher(pattern: &str) -> Option<RuleMatcher> {
+    let glob = |p: &str| Glob::new(p).ok().map(|g| g.compile_matcher());
+    match pattern {
+        "dir" => Some(RuleMatcher::Dir(None)),
+        "executable" => Some(RuleMatcher::Executable),
+        "symlink" => Some(RuleMatcher::Symlink),
+        "broken_symlink" => Some(RuleMatcher::BrokenSymlink),
+        _ => {
+            if let Some(name) = pattern.strip_prefix("dir:") {
+                Some(RuleMatcher::Dir(Some(glob(name)?)))
+            } else if pattern.contains('/') {
+                Some(RuleMatcher::Path(glob(&format!("**/{pattern}"))?))
+            } else {
+                Some(RuleMatcher::Name(glob(pattern)?))
+            }
+        }
+    }
+}
+
+/// Built-in directory and executable rules, applied after user rules and LS_COLORS.
+fn default_rules(ui: &UiTheme) -> Vec<StyleRule> {
+    vec![
+        StyleRule {
+            matcher: RuleMatcher::Dir(None),
+            style: ui.directory,
+        },
+        StyleRule {
+            matcher: RuleMatcher::Executable,
+            style: ui.executable,
+        },
+    ]
+}
+
+/// Resolves the display style of tree entries.
+///
+/// Priority:
+/// 1. Style rules (user rules, then theme rules), in order
+/// 2. LS_COLORS / dircolors entries (when enabled)
+/// 3. Theme directory and executable styles (bold blue / bold green by default)
+/// 4. Extension colors from the `ColorMap`
+/// 5. Theme file color (bright white by default)
+pub struct Styler {
+    color_map: ColorMap,
+    ls_colors: Option<LsColors>,
+    rules: Vec<StyleRule>,
+    defaults: Vec<StyleRule>,
+    ui: UiTheme,
+    /// Whether entries exist on the disk and may be inspected.
+    disk: bool,
+}
+
+impl Styler {
+    /// Creates a new `Styler` from extension colors, optional LS_COLORS rules,
+    /// style rules and the theme's UI colors.
+    pub fn new(
+        color_map: ColorMap,
+        ls_colors: Option<LsColors>,
+        rules: Vec<StyleRule>,
+        ui: UiTheme,
+    ) -> Self {
+        Styler {
+            color_map,
+            ls_colors,
+            rules,
+            defaults: default_rules(&ui),
+            ui,
+            disk: true,
+        }
+    }
+
+    /// Styles entries by their type and name alone, for trees that were not
+    /// scanned (`--fromfile`, `--load`): their paths are not looked up on
+    /// the disk, where they may not exist or may name unrelated files.
+    pub fn without_disk_lookups(mut self) -> Self {
+        self.disk = false;
+        self
+    }
+
+    /// Returns true if entries may be looked up on the disk (see
+    /// `without_disk_lookups`).
+    pub fn disk_lookups(&self) -> bool {
+        self.disk
+    }
+
+    /// Returns the theme colors for non-file elements.
+    pub fn ui(&self) -> &UiTheme {
+        &self.ui
+    }
+
+    /// Returns the style for the entry at `path`.
+    ///
+    /// `is_dir` comes from the tree rather than the disk, so entries of trees
+    /// that were not scanned (`--fromfile`) are styled correctly.
+    /// See `without_disk_lookups`.
+    /// A detected `kind` takes precedence over the extension when looking up
+    /// the extension color map.
+    pub fn style_for(
+        &self,
+        path: &Path,
+        is_dir: bool,
+        is_symlink: bool,
+        kind: Option<FileKind>,
+    ) -> TextStyle {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+
+        if let Some(rule) = self
+            .rules
+            .iter()
+            .find(|r| r.matches(path, &name, is_dir, is_symlink, self.disk))
+        {
+            return rule.style;
+        }
+
+        if let Some(ref ls) = self.ls_colors
+            && let Some(style) = if self.disk {
+                ls.style_for(path, is_dir, is_symlink)
+            } else {
+                ls.style_for_name(path, is_dir, is_symlink)
+            }
+        {
+            return style;
+        }
+
+        if let Some(rule) = self
+            .defaults
+            .iter()
+            .find(|r| r.matches(path, &name, is_dir, is_symlink, self.disk))
+        {
+            return rule.style;
+        }
+
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        let color = kind
+            .and_then(|k| self.color_map.get(k.ext))
+            .or_else(|| self.color_map.get(&ext));
+        match color {
+            Some(&(r, g, b)) => TextStyle::fg(StyleColor::Rgb(r, g, b)),
+            None => TextStyle::fg(self.ui.file),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::build_color_map;
+    use std::collections::HashMap;
+
+    fn test_styler(ls_colors: Option<LsColors>, rules: Vec<StyleRule>) -> Styler {
+        Styler::new(
+            build_color_map(&HashMap::new()),
+            ls_colors,
+            rules,
+            UiTheme::default(),
+        )
+    }
+
+    #[test]
+    fn styler_directory_is_bold_blue() {
+        let dir = tempfile::tempdir().unwrap();
+        let styler = test_styler(None, Vec::new());
+        let style = styler.style_for(dir.path(), true, false, None);
+        assert_eq!(style.fg, Some(StyleColor::Indexed(4)));
+        assert!(style.bold);
+    }
+
+    #[test]
+    fn styler_uses_extension_color() {
+        let styler = test_styler(None, Vec::new());
+        let style = styler.style_for(Path::new("missing/main.rs"), false, false, None);
+        assert_eq!(style.fg, Some(StyleColor::Rgb(255, 165, 0)));
+    }
+
+    #[test]
+    fn styler_prefers_ls_colors() {
+        let ls = LsColors::parse("*.rs=04;32");
+        let styler = test_styler(Some(ls), Vec::new());
+        let style = styler.style_for(Path::new("missing/main.rs"), false, false, None);
+        assert_eq!(style.fg, Some(StyleColor::Indexed(2)));
+        assert!(style.underline);
+    }
+
+    #[test]
+    fn styler_uses_detected_kind() {
+        let styler = test_styler(None, Vec::new());
+        let kind = crate::filetype::from_extension(Path::new("x.rs"));
+        let style = styler.style_for(Path::new("missing/deploy"), false, false, kind);
+        let expected = build_color_map(&HashMap::new())["rs"];
+        assert_eq!(
+            style.fg,
+            Some(StyleColor::Rgb(expected.0, expected.1, expected.2))
+        );
+    }
+
+    fn rule(pattern: &str, fg: &str) -> StyleRuleConfig {
+        StyleRuleConfig {
+            pattern: pattern.to_string(),
+            fg: Some(fg.to_string()),
+            ..StyleRuleConfig::default()
+        }
+    }
+
+    #[test]
+    fn rules_match_globs_in_order() {
+        let rules = build_style_rules(&[
+            rule("*_test.rs", "green"),
+            rule("*.rs", "red"),
+            rule("{README,LICENSE}*", "#00FF00"),
+        ]);
+        let styler = test_styler(None, rules);
+        let fg = |p: &str| styler.style_for(Path::new(p), false, false, None).fg;
+        assert_eq!(fg("src/tree_test.rs"), Some(StyleColor::Rgb(0, 205, 0)));
+        assert_eq!(fg("src/tree.rs"), Some(StyleColor::Rgb(205, 0, 0)));
+        assert_eq!(fg("README.md"), Some(StyleColor::Rgb(0, 255, 0)));
+    }
+
+    #[test]
+    fn dir_class_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::create_dir(dir.path().join("docs")).unwrap();
+        let mut config = rule("dir:src", "yellow");
+        config.underline = true;
+        let styler = test_styler(None, build_style_rules(&[config]));
+        let src = styler.style_for(&dir.path().join("src"), true, false, None);
+        assert_eq!(src.fg, Some(StyleColor::Rgb(205, 205, 0)));
+        assert!(src.underline);
+        // Other directories keep the built-in style
+        let docs = styler.style_for(&dir.path().join("docs"), true, false, None);
+        assert_eq!(docs.fg, Some(StyleColor::Indexed(4)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn broken_symlink_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("dangling");
+        std::os::unix::fs::symlink(dir.path().join("nowhere"), &link).unwrap();
+        let rules = build_style_rules(&[rule("broken_symlink", "red")]);
+        let styler = test_styler(None, rules);
+        assert_eq!(
+            styler.style_for(&link, false, true, None).fg,
+            Some(StyleColor::Rgb(205, 0, 0))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn synthetic_entries_skip_disk_lookups() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("deploy");
+        fs::write(&script, "").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        let link = dir.path().join("dangling");
+        std::os::unix::fs::symlink(dir.path().join("nowhere"), &link).unwrap();
+
+        let ls_colors = LsColors::parse("ln=01;36:or=40;31;01");
+        let styler = test_styler(Some(ls_colors), Vec::new());
+        assert_eq!(
+            styler.style_for(&script, false, false, None),
+            UiTheme::default().executable
+        );
+        assert_eq!(
+            styler.style_for(&link, false, true, None).bg,
+            Some(StyleColor::Indexed(0))
+        );
+
+        let styler = styler.without_disk_lookups();
+        assert_eq!(
+            styler.style_for(&script, false, false, None),
+            TextStyle::fg(UiTheme::default().file)
+        );
+        let link_style = styler.style_for(&link, false, true, None);
+        assert_eq!(link_style.fg, Some(StyleColor::Indexed(6)));
+        assert_eq!(link_style.bg, None);
+    }
+
+    #[test]
+    fn invalid_rules_are_skipped() {
+        let rules = build_style_rules(&[rule("[", "red"), rule("*.rs", "nope")]);
+        assert!(rules.is_empty());
+    }
+}
@@ -0,0 +1,381 @@
+//! Terminal capability helpers.
+//!
+//! This module decides which terminal features Kree may use for the current
+//! output stream and produces the escape sequences for them, such as OSC 8
+//! hyperlinks and colors quantized to the supported palette.
+
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use clap::ValueEnum;
+
+/// Color depth supported by the output terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorLevel {
+    /// No colors at all.
+    None,
+    /// The 16 basic ANSI colors.
+    Ansi16,
+    /// The xterm 256-color palette.
+    Ansi256,
+    /// 24-bit RGB colors.
+    TrueColor,
+}
+
+impl ColorLevel {
+    /// Detects the color level for stdout from the environment.
+    ///
+    /// Honors `NO_COLOR` and `CLICOLOR_FORCE`, disables colors when stdout is
+    /// not a terminal or `TERM` is `dumb`, and reads the depth from
+    /// `COLORTERM` and `TERM`. Windows consoles set no `TERM`, so an unset
+    /// `TERM` means 16 colors, or truecolor in Windows Terminal
+    /// (`WT_SESSION`).
+    pub fn detect() -> Self {
+        Self::from_env(std::io::stdout().is_terminal(), |key| {
+            std::env::var(key).ok()
+        })
+    }
+
+    fn from_env(is_tty: bool, var: impl Fn(&str) -> Option<String>) -> Self {
+        let forced = var("CLICOLOR_FORCE").is_some_and(|v| !v.is_empty() && v != "0");
+        let no_color = var("NO_COLOR").is_some_and(|v| !v.is_empty());
+        if !forced && (no_color || !is_tty) {
+            return ColorLevel::None;
+        }
+
+        if var("COLORTERM").is_some_and(|v| v == "truecolor" || v == "24bit") {
+            return ColorLevel::TrueColor;
+        }
+
+        let term = var("TERM").unwrap_or_default();
+        if term == "dumb" && !forced {
+            ColorLevel::None
+        } else if term.contains("256color") {
+            ColorLevel::Ansi256
+        } else if term.is_empty() && var("WT_SESSION").is_some() {
+            ColorLevel::TrueColor
+        } else {
+            ColorLevel::Ansi16
+        }
+    }
+}
+
+static COLOR_LEVEL: AtomicU8 = AtomicU8::new(ColorLevel::TrueColor as u8);
+
+/// Sets the process-wide color level used when converting RGB colors.
+pub fn set_color_level(level: ColorLevel) {
+    COLOR_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Returns the process-wide color level (truecolor unless set otherwise).
+pub fn color_level() -> ColorLevel {
+    match COLOR_LEVEL.load(Ordering::Relaxed) {
+        0 => ColorLevel::None,
+        1 => ColorLevel::Ansi16,
+        2 => ColorLevel::Ansi256,
+        _ => ColorLevel::TrueColor,
+    }
+}
+
+/// RGB values of the 16 basic ANSI colors (xterm defaults), in palette order.
+pub const ANSI16_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Channel values used by the 6x6x6 color cube of the 256-color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let dr = r1.abs_diff(r2) as u32;
+    let dg = g1.abs_diff(g2) as u32;
+    let db = b1.abs_diff(b2) as u32;
+    dr * dr + dg * dg + db * db
+}
+
+/// Returns the index (0-15) of the closest basic ANSI color.
+pub fn nearest_ansi16(rgb: (u8, u8, u8)) -> u8 {
+    ANSI16_PALETTE
+        .iter()
+        .enumerate()
+        .min_by_key(|&(_, &c)| distance(rgb, c))
+        .map(|(i, _)| i as u8)
+        .unwrap_or(7)
+}
+
+/// Returns the index (16-255) of the closest color in the xterm 256-color palette.
+///
+/// Picks whichever is closer between the 6x6x6 color cube and the grayscale ramp.
+pub fn nearest_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    let cube_index = |v: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &l)| l.abs_diff(v))
+            .map(|(i, _)| i as u8)
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (cube_index(rgb.0), cube_index(rgb.1), cube_index(rgb.2));
+    let cube_rgb = (
+        CUBE_LEVELS[ri as usize],
+        CUBE_LEVELS[gi as usize],
+        CUBE_LEVELS[bi as usize],
+    );
+    let cube = 16 + 36 * ri + 6 * gi + bi;
+
+    let avg = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray_step = (avg.saturating_sub(8) / 10).min(23) as u8;
+    let gray_value = 8 + 10 * gray_step;
+    let gray = 232 + gray_step;
+
+    if distance(rgb, (gray_value, gray_value, gray_value)) < distance(rgb, cube_rgb) {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// Returns the RGB value of an entry of the xterm 256-color palette.
+pub fn ansi256_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI16_PALETTE[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let v = 8 + 10 * (index - 232);
+            (v, v, v)
+        }
+    }
+}
+
+/// Converts an RGB tuple into the closest `colored` color the terminal supports.
+pub fn colored_color((r, g, b): (u8, u8, u8)) -> colored::Color {
+    use colored::Color;
+
+    match color_level() {
+        ColorLevel::TrueColor | ColorLevel::None => Color::TrueColor { r, g, b },
+        ColorLevel::Ansi256 => Color::AnsiColor(nearest_ansi256((r, g, b))),
+        ColorLevel::Ansi16 => match nearest_ansi16((r, g, b)) {
+            0 => Color::Black,
+            1 => Color::Red,
+            2 => Color::Green,
+            3 => Color::Yellow,
+            4 => Color::Blue,
+            5 => Color::Magenta,
+            6 => Color::Cyan,
+            7 => Color::White,
+            8 => Color::BrightBlack,
+            9 => Color::BrightRed,
+            10 => Color::BrightGreen,
+            11 => Color::BrightYellow,
+            12 => Color::BrightBlue,
+            13 => Color::BrightMagenta,
+            14 => Color::BrightCyan,
+            _ => Color::BrightWhite,
+        },
+    }
+}
+
+/// Controls when file names are wrapped in OSC 8 hyperlinks.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum HyperlinkMode {
+    /// Emit hyperlinks only when stdout is a terminal.
+    Auto,
+    /// Always emit hyperlinks, even when the output is piped.
+    Always,
+    /// Never emit hyperlinks.
+    Never,
+}
+
+impl HyperlinkMode {
+    /// Resolves the mode into a yes/no decision for stdout.
+    pub fn enabled(self) -> bool {
+        match self {
+            HyperlinkMode::Auto => std::io::stdout().is_terminal(),
+            HyperlinkMode::Always => true,
+            HyperlinkMode::Never => false,
+        }
+    }
+}
+
+/// Wraps `text` in an OSC 8 hyperlink pointing at `path`.
+///
+/// When `line` is given it is appended as a `#line` fragment, which terminals
+/// such as kitty and WezTerm use to open the file at that line. Terminals
+/// without OSC 8 support simply print `text`.
+pub fn hyperlink(text: &str, path: &Path, line: Option<usize>) -> String {
+    let url = file_url(path, line);
+    format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\")
+}
+
+/// Builds a percent-encoded `file://` URL for `path`.
+fn file_url(path: &Path, line: Option<usize>) -> String {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| PathBuf::from(path));
+    let path_str = absolute.to_string_lossy().replace('\\', "/");
+
+    let mut url = format!("file://{}", hostname());
+    if !path_str.starts_with('/') {
+        // Windows drive paths (C:/...) need a leading slash in URLs
+        url.push('/');
+    }
+    for byte in path_str.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' | b':' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    if let Some(line) = line {
+        url.push_str(&format!("#{line}"));
+    }
+    url
+}
+
+/// Returns the local hostname, cached after the first call.
+///
+/// Including the host lets terminals refuse links coming from a remote
+/// session instead of opening a wrong local file.
+fn hostname() -> &'static str {
+    static HOSTNAME: OnceLock<String> = OnceLock::new();
+    HOSTNAME.get_or_init(|| {
+        #[cfg(unix)]
+        {
+            let mut buf = [0u8; 256];
+            let ret =
+                unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+            if ret == 0 {
+                let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+                return String::from_utf8_lossy(&buf[..len]).into_owned();
+            }
+        }
+        String::new()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hyperlink_wraps_text() {
+        let link = hyperlink("main.rs", Path::new("/tmp/main.rs"), None);
+        assert!(link.starts_with("\x1b]8;;file://"));
+        assert!(link.contains("/tmp/main.rs\x1b\\main.rs"));
+        assert!(link.ends_with("\x1b]8;;\x1b\\"));
+    }
+
+    #[test]
+    fn file_url_percent_encodes() {
+        let url = file_url(Path::new("/tmp/my file#1.rs"), None);
+        assert!(url.ends_with("/tmp/my%20file%231.rs"));
+    }
+
+    #[test]
+    fn file_url_appends_line() {
+        let url = file_url(Path::new("/tmp/main.rs"), Some(42));
+        assert!(url.ends_with("/tmp/main.rs#42"));
+    }
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |key| {
+            vars.iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn detect_truecolor() {
+        let vars = [("COLORTERM", "truecolor"), ("TERM", "xterm-256color")];
+        assert_eq!(
+            ColorLevel::from_env(true, env(&vars)),
+            ColorLevel::TrueColor
+        );
+    }
+
+    #[test]
+    fn detect_256_and_16() {
+        let vars = [("TERM", "screen-256color")];
+        assert_eq!(ColorLevel::from_env(true, env(&vars)), ColorLevel::Ansi256);
+        let vars = [("TERM", "screen")];
+        assert_eq!(ColorLevel::from_env(true, env(&vars)), ColorLevel::Ansi16);
+    }
+
+    #[test]
+    fn detect_without_term() {
+        // Windows consoles set no TERM
+        assert_eq!(ColorLevel::from_env(true, env(&[])), ColorLevel::Ansi16);
+        let vars = [("WT_SESSION", "5c1d3c0e")];
+        assert_eq!(
+            ColorLevel::from_env(true, env(&vars)),
+            ColorLevel::TrueColor
+        );
+        let vars = [("TERM", "dumb")];
+        assert_eq!(ColorLevel::from_env(true, env(&vars)), ColorLevel::None);
+    }
+
+    #[test]
+    fn detect_disabled_when_piped_or_no_color() {
+        let vars = [("COLORTERM", "truecolor")];
+        assert_eq!(ColorLevel::from_env(false, env(&vars)), ColorLevel::None);
+        let vars = [("COLORTERM", "truecolor"), ("NO_COLOR", "1")];
+        assert_eq!(ColorLevel::from_env(true, env(&vars)), ColorLevel::None);
+    }
+
+    #[test]
+    fn detect_clicolor_force_overrides() {
+        let vars = [("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")];
+        assert_eq!(ColorLevel::from_env(false, env(&vars)), ColorLevel::Ansi16);
+    }
+
+    #[test]
+    fn quantize_to_256() {
+        assert_eq!(nearest_ansi256((255, 0, 0)), 196);
+        assert_eq!(nearest_ansi256((128, 128, 128)), 244);
+        assert_eq!(nearest_ansi256((255, 165, 0)), 214);
+    }
+
+    #[test]
+    fn quantize_to_16() {
+        assert_eq!(nearest_ansi16((250, 10, 10)), 9);
+        assert_eq!(nearest_ansi16((0, 0, 200)), 4);
+        assert_eq!(nearest_ansi16((120, 120, 120)), 8);
+    }
+
+    #[test]
+    fn ansi256_round_trip() {
+        assert_eq!(ansi256_to_rgb(214), (255, 175, 0));
+        assert_eq!(ansi256_to_rgb(244), (128, 128, 128));
+        assert_eq!(nearest_ansi256(ansi256_to_rgb(100)), 100);
+    }
+
+    #[test]
+    fn never_mode_disabled() {
+        assert!(!HyperlinkMode::Never.enabled());
+        assert!(HyperlinkMode::Always.enabled());
+    }
+}
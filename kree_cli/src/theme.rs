@@ -0,0 +1,444 @@
+//! Named color themes.
+//!
+//! A theme bundles extension colors, icons, style rules and the colors of
+//! non-file elements: tree lines, metadata, and the TUI selection, search
+//! matches, status bar and borders. Themes are selected with
+//! `theme = "<name>"` in `~/.kreerc` (or `--theme`) and are looked up first in
+//! `~/.kree/themes/<name>.toml`, then among the built-in themes.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::config::StyleRuleConfig;
+use crate::render::parse_color;
+use crate::style::{StyleColor, TextStyle};
+
+/// Built-in themes as TOML documents, in the same format as user theme files.
+const BUILTIN_THEMES: &[(&str, &str)] = &[
+    ("default", ""),
+    (
+        "gruvbox-dark",
+        r##"
+        [ui]
+        directory = "#83a598"
+        executable = "#b8bb26"
+        file = "#ebdbb2"
+        symlink = "#8ec07c"
+        tree_lines = "#665c54"
+        metadata = "#928374"
+        metadata_dim = false
+        selection_bg = "#504945"
+        selection_fg = "#fbf1c7"
+        search_match = "#fabd2f"
+        accent = "#fabd2f"
+        accent_fg = "#282828"
+        title = "#fe8019"
+        highlight = "#fabd2f"
+        border = "#665c54"
+        error = "#fb4934"
+        line_numbers = "#7c6f64"
+
+        [colors]
+        rs = "#fe8019"
+        py = "#b8bb26"
+        js = "#fabd2f"
+        ts = "#fabd2f"
+        json = "#8ec07c"
+        toml = "#8ec07c"
+        yaml = "#8ec07c"
+        yml = "#8ec07c"
+        md = "#d3869b"
+        txt = "#d5c4a1"
+        lock = "#7c6f64"
+        "##,
+    ),
+    (
+        "gruvbox-light",
+        r##"
+        [ui]
+        directory = "#076678"
+        executable = "#79740e"
+        file = "#3c3836"
+        symlink = "#427b58"
+        tree_lines = "#a89984"
+        metadata = "#7c6f64"
+        metadata_dim = false
+        selection_bg = "#d5c4a1"
+        selection_fg = "#282828"
+        search_match = "#b57614"
+        accent = "#076678"
+        accent_fg = "#fbf1c7"
+        title = "#af3a03"
+        highlight = "#b57614"
+        border = "#bdae93"
+        error = "#9d0006"
+        line_numbers = "#928374"
+
+        [colors]
+        rs = "#af3a03"
+        py = "#79740e"
+        js = "#b57614"
+        ts = "#b57614"
+        json = "#427b58"
+        toml = "#427b58"
+        yaml = "#427b58"
+        yml = "#427b58"
+        md = "#8f3f71"
+        txt = "#504945"
+        lock = "#928374"
+        "##,
+    ),
+    (
+        "solarized-dark",
+        r##"
+        [ui]
+        directory = "#268bd2"
+        executable = "#859900"
+        file = "#93a1a1"
+        symlink = "#2aa198"
+        tree_lines = "#586e75"
+        metadata = "#586e75"
+        metadata_dim = false
+        selection_bg = "#073642"
+        selection_fg = "#eee8d5"
+        search_match = "#b58900"
+        accent = "#268bd2"
+        accent_fg = "#002b36"
+        title = "#cb4b16"
+        highlight = "#b58900"
+        border = "#586e75"
+        error = "#dc322f"
+        line_numbers = "#586e75"
+
+        [colors]
+        rs = "#cb4b16"
+        py = "#859900"
+        js = "#b58900"
+        ts = "#b58900"
+        json = "#2aa198"
+        toml = "#2aa198"
+        yaml = "#2aa198"
+        yml = "#2aa198"
+        md = "#d33682"
+        lock = "#586e75"
+        "##,
+    ),
+    (
+        "solarized-light",
+        r##"
+        [ui]
+        directory = "#268bd2"
+        executable = "#859900"
+        file = "#586e75"
+        symlink = "#2aa198"
+        tree_lines = "#93a1a1"
+        metadata = "#93a1a1"
+        metadata_dim = false
+        selection_bg = "#eee8d5"
+        selection_fg = "#073642"
+        search_match = "#b58900"
+        accent = "#268bd2"
+        accent_fg = "#fdf6e3"
+        title = "#cb4b16"
+        highlight = "#b58900"
+        border = "#93a1a1"
+        error = "#dc322f"
+        line_numbers = "#93a1a1"
+
+        [colors]
+        rs = "#cb4b16"
+        py = "#859900"
+        js = "#b58900"
+        ts = "#b58900"
+        json = "#2aa198"
+        toml = "#2aa198"
+        yaml = "#2aa198"
+        yml = "#2aa198"
+        md = "#d33682"
+        lock = "#93a1a1"
+        "##,
+    ),
+    (
+        "nord",
+        r##"
+        [ui]
+        directory = "#81a1c1"
+        executable = "#a3be8c"
+        file = "#d8dee9"
+        symlink = "#88c0d0"
+        tree_lines = "#4c566a"
+        metadata = "#616e88"
+        metadata_dim = false
+        selection_bg = "#3b4252"
+        selection_fg = "#eceff4"
+        search_match = "#ebcb8b"
+        accent = "#88c0d0"
+        accent_fg = "#2e3440"
+        title = "#88c0d0"
+        highlight = "#ebcb8b"
+        border = "#4c566a"
+        error = "#bf616a"
+        line_numbers = "#4c566a"
+
+        [colors]
+        rs = "#d08770"
+        py = "#a3be8c"
+        js = "#ebcb8b"
+        ts = "#ebcb8b"
+        json = "#8fbcbb"
+        toml = "#8fbcbb"
+        yaml = "#8fbcbb"
+        yml = "#8fbcbb"
+        md = "#b48ead"
+        lock = "#4c566a"
+        "##,
+    ),
+];
+
+/// Colors of everything that is not a file name.
+#[derive(Clone, Debug)]
+pub struct UiTheme {
+    /// Style for directory names.
+    pub directory: TextStyle,
+    /// Style for executable files.
+    pub executable: TextStyle,
+    /// Color for files without a more specific style.
+    pub file: StyleColor,
+    /// Color of the `-> target` suffix of symlinks.
+    pub symlink: StyleColor,
+    /// Color of the tree-drawing characters. When unset, the tree output leaves
+    /// them uncolored and the TUI draws them in dark gray.
+    pub tree_lines: Option<StyleColor>,
+    /// Style for the metadata columns of `--long`.
+    pub metadata: TextStyle,
+    /// Background of the TUI cursor line.
+    pub selection_bg: StyleColor,
+    /// Foreground of the TUI cursor line (keeps the entry color when unset).
+    pub selection_fg: Option<StyleColor>,
+    /// Color of search matches in the TUI (always underlined).
+    pub search_match: Option<StyleColor>,
+    /// Background of the key badges in the TUI status bar.
+    pub accent: StyleColor,
+    /// Foreground of the key badges in the TUI status bar.
+    pub accent_fg: StyleColor,
+    /// Color of the TUI title.
+    pub title: StyleColor,
+    /// Color of the breadcrumb path, search prompt and expand indicators.
+    pub highlight: StyleColor,
+    /// Color of TUI borders (terminal default when unset).
+    pub border: Option<StyleColor>,
+    /// Color of error messages in the TUI.
+    pub error: StyleColor,
+    /// Color of line numbers in the TUI preview.
+    pub line_numbers: StyleColor,
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        UiTheme {
+            directory: TextStyle::fg(StyleColor::Indexed(4)).bold(),
+            executable: TextStyle::fg(StyleColor::Indexed(2)).bold(),
+            file: StyleColor::Indexed(15),
+            symlink: StyleColor::Indexed(6),
+            tree_lines: None,
+            metadata: TextStyle {
+                dim: true,
+                ..TextStyle::default()
+            },
+            selection_bg: StyleColor::Indexed(8),
+            selection_fg: None,
+            search_match: None,
+            accent: StyleColor::Indexed(6),
+            accent_fg: StyleColor::Indexed(0),
+            title: StyleColor::Indexed(6),
+            highlight: StyleColor::Indexed(3),
+            border: None,
+            error: StyleColor::Indexed(1),
+            line_numbers: StyleColor::Indexed(8),
+        }
+    }
+}
+
+/// A resolved theme.
+#[derive(Default)]
+pub struct Theme {
+    /// Extension colors, overridden by the user's `[colors]`.
+    pub colors: HashMap<String, String>,
+    /// Icons, overridden by the user's `[icons]`.
+    pub icons: HashMap<String, String>,
+    /// Style rules, evaluated after the user's `[[styles]]`.
+    pub styles: Vec<StyleRuleConfig>,
+    /// Colors of non-file elements.
+    pub ui: UiTheme,
+}
+
+/// On-disk theme file format.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ThemeFile {
+    colors: HashMap<String, String>,
+    icons: HashMap<String, String>,
+    styles: Vec<StyleRuleConfig>,
+    ui: UiConfig,
+}
+
+/// `[ui]` section of a theme file. Every value is a color name or hex code.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct UiConfig {
+    directory: Option<String>,
+    executable: Option<String>,
+    file: Option<String>,
+    symlink: Option<String>,
+    tree_lines: Option<String>,
+    metadata: Option<String>,
+    metadata_dim: Option<bool>,
+    selection_bg: Option<String>,
+    selection_fg: Option<String>,
+    search_match: Option<String>,
+    accent: Option<String>,
+    accent_fg: Option<String>,
+    title: Option<String>,
+    highlight: Option<String>,
+    border: Option<String>,
+    error: Option<String>,
+    line_numbers: Option<String>,
+}
+
+impl Theme {
+    /// Loads the theme called `name`.
+    ///
+    /// User theme files in `~/.kree/themes/` take precedence over built-in
+    /// themes of the same name. Unknown or malformed themes print a warning
+    /// and fall back to the default theme.
+    pub fn load(name: &str) -> Self {
+        if let Some(path) = themes_dir().map(|d| d.join(format!("{name}.toml")))
+            && let Ok(contents) = fs::read_to_string(&path)
+        {
+            return Self::parse(&contents).unwrap_or_else(|e| {
+                eprintln!("Warning: malformed theme file {}: {e}", path.display());
+                Self::default()
+            });
+        }
+
+        match BUILTIN_THEMES.iter().find(|(n, _)| *n == name) {
+            Some((_, source)) => Self::parse(source).unwrap_or_default(),
+            None => {
+                eprintln!("Warning: unknown theme '{name}', using the default theme");
+                Self::default()
+            }
+        }
+    }
+
+    /// Parses a theme from its TOML source.
+    fn parse(source: &str) -> Result<Self, toml::de::Error> {
+        let file: ThemeFile = toml::from_str(source)?;
+        let mut ui = UiTheme::default();
+        let ui_cfg = &file.ui;
+
+        let color = |value: &Option<String>, key: &str| -> Option<StyleColor> {
+            let value = value.as_ref()?;
+            match parse_color(value) {
+                Some((r, g, b)) => Some(StyleColor::Rgb(r, g, b)),
+                None => {
+                    eprintln!("Warning: unknown color '{value}' for theme key '{key}', ignoring");
+                    None
+                }
+            }
+        };
+
+        if let Some(c) = color(&ui_cfg.directory, "directory") {
+            ui.directory = TextStyle::fg(c).bold();
+        }
+        if let Some(c) = color(&ui_cfg.executable, "executable") {
+            ui.executable = TextStyle::fg(c).bold();
+        }
+        if let Some(c) = color(&ui_cfg.metadata, "metadata") {
+            ui.metadata.fg = Some(c);
+        }
+        if let Some(dim) = ui_cfg.metadata_dim {
+            ui.metadata.dim = dim;
+        }
+        ui.file = color(&ui_cfg.file, "file").unwrap_or(ui.file);
+        ui.symlink = color(&ui_cfg.symlink, "symlink").unwrap_or(ui.symlink);
+        ui.tree_lines = color(&ui_cfg.tree_lines, "tree_lines").or(ui.tree_lines);
+        ui.selection_bg = color(&ui_cfg.selection_bg, "selection_bg").unwrap_or(ui.selection_bg);
+        ui.selection_fg = color(&ui_cfg.selection_fg, "selection_fg").or(ui.selection_fg);
+        ui.search_match = color(&ui_cfg.search_match, "search_match").or(ui.search_match);
+        ui.accent = color(&ui_cfg.accent, "accent").unwrap_or(ui.accent);
+        ui.accent_fg = color(&ui_cfg.accent_fg, "accent_fg").unwrap_or(ui.accent_fg);
+        ui.title = color(&ui_cfg.title, "title").unwrap_or(ui.title);
+        ui.highlight = color(&ui_cfg.highlight, "highlight").unwrap_or(ui.highlight);
+        ui.border = color(&ui_cfg.border, "border").or(ui.border);
+        ui.error = color(&ui_cfg.error, "error").unwrap_or(ui.error);
+        ui.line_numbers = color(&ui_cfg.line_numbers, "line_numbers").unwrap_or(ui.line_numbers);
+
+        Ok(Theme {
+            colors: file.colors,
+            icons: file.icons,
+            styles: file.styles,
+            ui,
+        })
+    }
+
+    /// Returns the theme's extension colors with `user` entries on top.
+    pub fn colors_with(&self, user: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut merged = self.colors.clone();
+        merged.extend(user.iter().map(|(k, v)| (k.clone(), v.clone())));
+        merged
+    }
+
+    /// Returns the theme's icons with `user` entries on top.
+    pub fn icons_with(&self, user: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut merged = self.icons.clone();
+        merged.extend(user.iter().map(|(k, v)| (k.clone(), v.clone())));
+        merged
+    }
+}
+
+/// Directory holding user theme files (`~/.kree/themes`).
+fn themes_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".kree").join("themes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_themes_parse() {
+        for (name, source) in BUILTIN_THEMES {
+            assert!(Theme::parse(source).is_ok(), "theme {name} failed to parse");
+        }
+    }
+
+    #[test]
+    fn theme_ui_colors() {
+        let theme = Theme::parse(
+            r##"
+            [ui]
+            directory = "#010203"
+            selection_bg = "red"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(theme.ui.directory.fg, Some(StyleColor::Rgb(1, 2, 3)));
+        assert!(theme.ui.directory.bold);
+        assert_eq!(theme.ui.selection_bg, StyleColor::Rgb(205, 0, 0));
+        // Unset keys keep their defaults
+        assert_eq!(theme.ui.accent, StyleColor::Indexed(6));
+    }
+
+    #[test]
+    fn user_colors_override_theme() {
+        let theme = Theme::parse("[colors]\nrs = \"red\"\nmd = \"blue\"\n").unwrap();
+        let mut user = HashMap::new();
+        user.insert("rs".to_string(), "green".to_string());
+        let merged = theme.colors_with(&user);
+        assert_eq!(merged.get("rs").map(String::as_str), Some("green"));
+        assert_eq!(merged.get("md").map(String::as_str), Some("blue"));
+    }
+}
@@ -1,11 +1,14 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 use clap::ValueEnum;
 use rayon::prelude::*;
 
+use crate::filetype::{self, FileKind};
+use crate::hash::{self, HashAlgorithm};
 use crate::ignore::IgnoreFilter;
+use crate::loc::{self, Languages, LineCounts};
 
 /// Options controlling which entries are included in the tree traversal.
 #[derive(Clone, Default)]
@@ -17,8 +20,25 @@ pub struct TreeOptions {
     pub extensions: Vec<String>,
     /// Collect and display file metadata (size, permissions, modified date, owner).
     pub show_metadata: bool,
+    /// Detect file kinds from content and well-known names. The extensions
+    /// filter then also matches detected kinds (e.g. `py` selects a script
+    /// with a Python shebang).
+    pub detect_kinds: bool,
+    /// Merge chains of single-child directories (`src/main/java`) into one
+    /// node. Applied by `compact_tree` after loading.
+    pub compact: bool,
+    /// Compute a digest of every file's contents with this algorithm.
+    pub hash: Option<HashAlgorithm>,
+    /// Count the code, comment and blank lines of every file. Directory
+    /// totals are filled in by `loc::sum_tree`, except at the depth limit,
+    /// where `load_tree` counts everything below the directory.
+    pub loc: bool,
 }
 
+/// Levels below a directory at the depth limit that `--loc` still counts,
+/// the same safety limit the CLI applies to whole-tree scans.
+const LOC_DEPTH: u32 = 60;
+
 /// Specifies how entries should be sorted in the tree.
 #[derive(Clone, Copy, ValueEnum)]
 pub enum SortMode {
@@ -44,11 +64,14 @@ pub struct NodeMetadata {
 }
 
 /// Represents a node in the directory tree.
+#[derive(Default)]
 pub struct TreeNode {
     /// Name of the file or directory.
     pub name: String,
     /// Full path to the node.
     pub path: PathBuf,
+    /// True if this node is a directory (or a symlink to one).
+    pub is_dir: bool,
     /// List of children nodes (empty for files).
     pub children: Vec<TreeNode>,
     /// True if this node is a symbolic link.
@@ -57,6 +80,51 @@ pub struct TreeNode {
     pub symlink_target: Option<PathBuf>,
     /// Optional file metadata.
     pub metadata: Option<NodeMetadata>,
+    /// Detected file kind, when detection is enabled.
+    pub file_kind: Option<FileKind>,
+    /// Names of the directories merged into this node by `--compact`,
+    /// outermost first. Empty for regular nodes.
+    pub merged: Vec<String>,
+    /// Description from `.kreenotes` or a doc comment, set by `annotate_tree`.
+    pub note: Option<String>,
+    /// Hex digest of the file's contents, when hashing is enabled.
+    pub hash: Option<String>,
+    /// Line counts of the file, or the total of the files below a
+    /// directory, when `--loc` is enabled.
+    pub loc: Option<LineCounts>,
+    /// Per-language line counts of everything below a directory cut off by
+    /// the depth limit; empty for other nodes.
+    pub loc_languages: Languages,
+}
+
+impl TreeNode {
+    /// Returns true if this directory's only entry is a subdirectory that
+    /// `--compact` can merge into it.
+    pub fn has_single_dir_child(&self) -> bool {
+        match self.children.as_slice() {
+            [child] => !child.is_symlink && child.is_dir,
+            _ => false,
+        }
+    }
+
+    /// Merges the only child directory into this node, turning `a` with the
+    /// single child `b` into `a/b`.
+    pub fn merge_single_child(&mut self) {
+        let Some(child) = self.children.pop() else {
+            return;
+        };
+        if self.merged.is_empty() {
+            self.merged.push(self.name.clone());
+        }
+        self.merged.push(child.name);
+        self.name = self.merged.join("/");
+        self.path = child.path;
+        self.metadata = child.metadata;
+        self.note = self.note.take().or(child.note);
+        self.loc = child.loc;
+        self.loc_languages = child.loc_languages;
+        self.children = child.children;
+    }
 }
 
 /// Builds a tree structure from the filesystem starting at the given root.
@@ -105,20 +173,52 @@ pub fn load_tree(
         None
     };
 
+    let file_kind = if opts.detect_kinds {
+        filetype::detect(root)
+    } else {
+        None
+    };
+
+    let is_dir = root.is_dir();
+    // Runs on the rayon worker that loads this entry, so files are hashed
+    // in parallel
+    let hash = match opts.hash {
+        Some(algorithm) if !is_dir => hash::hash_file(root, algorithm),
+        _ => None,
+    };
+    let loc = if opts.loc && !is_dir && !is_symlink {
+        loc::count_file(root, file_kind)
+    } else {
+        None
+    };
+
     let mut node = TreeNode {
         name,
         path: root.clone(),
+        is_dir,
         children: Vec::new(),
         is_symlink,
         symlink_target,
         metadata,
+        file_kind,
+        merged: Vec::new(),
+        note: None,
+        hash,
+        loc,
+        loc_languages: Languages::new(),
     };
 
     if current_depth >= max_depth {
+        // Line totals of a directory cover everything below it, so they
+        // don't change when the TUI loads its children later
+        if opts.loc && node.is_dir && !node.is_symlink {
+            node.loc_languages = count_lines_below(root, LOC_DEPTH, filter, opts);
+            node.loc = loc::total(&node.loc_languages);
+        }
         return node;
     }
 
-    if !root.is_dir() {
+    if !node.is_dir {
         return node;
     }
 
@@ -130,27 +230,7 @@ pub fn load_tree(
     // Collect and filter entries first, then process in parallel
     let filtered_paths: Vec<PathBuf> = entries
         .flatten()
-        .filter(|entry| {
-            let file_name = entry.file_name().to_string_lossy().into_owned();
-            if filter.is_ignored(&file_name) {
-                return false;
-            }
-            let child_path = entry.path();
-            if opts.dirs_only && !child_path.is_dir() {
-                return false;
-            }
-            if !opts.extensions.is_empty() && !child_path.is_dir() {
-                let ext = child_path
-                    .extension()
-                    .and_then(|e| e.to_str())
-                    .unwrap_or("")
-                    .to_lowercase();
-                if !opts.extensions.contains(&ext) {
-                    return false;
-                }
-            }
-            true
-        })
+        .filter(|entry| is_included(entry, filter, opts))
         .map(|entry| entry.path())
         .collect();
 
@@ -159,23 +239,186 @@ pub fn load_tree(
         .map(|child_path| load_tree(child_path, max_depth, current_depth + 1, filter, sort, opts))
         .collect();
 
+    sort_children(&mut children, sort);
+    node.children = children;
+
+    node
+}
+
+/// Applies the ignore rules, dirs-only and extension filters to a directory
+/// entry.
+fn is_included(entry: &fs::DirEntry, filter: &IgnoreFilter, opts: &TreeOptions) -> bool {
+    let file_name = entry.file_name().to_string_lossy().into_owned();
+    if filter.is_ignored(&file_name) {
+        return false;
+    }
+    let child_path = entry.path();
+    if opts.dirs_only && !child_path.is_dir() {
+        return false;
+    }
+    if !opts.extensions.is_empty() && !child_path.is_dir() {
+        let ext = child_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        if !opts.extensions.contains(&ext) {
+            let detected = opts.detect_kinds
+                && filetype::detect(&child_path)
+                    .is_some_and(|k| opts.extensions.iter().any(|e| k.matches(e)));
+            if !detected {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Counts the lines of the files below `dir` by language, with the filters
+/// of `load_tree` but without building nodes. Symlinks are not followed, so
+/// a link such as `self -> .` is not counted over and over.
+fn count_lines_below(
+    dir: &Path,
+    depth: u32,
+    filter: &IgnoreFilter,
+    opts: &TreeOptions,
+) -> Languages {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Languages::new();
+    };
+    let paths: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| !t.is_symlink()))
+        .filter(|entry| is_included(entry, filter, opts))
+        .map(|entry| entry.path())
+        .collect();
+
+    paths
+        .par_iter()
+        .map(|path| {
+            let mut languages = Languages::new();
+            if path.is_dir() {
+                if depth > 1 {
+                    languages = count_lines_below(path, depth - 1, filter, opts);
+                }
+            } else {
+                let kind = if opts.detect_kinds {
+                    filetype::detect(path)
+                } else {
+                    None
+                };
+                if let Some(counts) = loc::count_file(path, kind) {
+                    loc::add_file(&mut languages, path, kind, counts);
+                }
+            }
+            languages
+        })
+        .reduce(Languages::new, |mut languages, other| {
+            loc::merge_languages(&mut languages, &other);
+            languages
+        })
+}
+
+/// Sorts sibling nodes according to `sort`.
+pub fn sort_children(children: &mut [TreeNode], sort: SortMode) {
     match sort {
         SortMode::Name => {
-            children.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+            children.sort_by_key(|a| a.name.to_lowercase());
         }
         SortMode::Kind => {
             children.sort_by(|a, b| {
-                let a_is_dir = a.path.is_dir();
-                let b_is_dir = b.path.is_dir();
-                b_is_dir
-                    .cmp(&a_is_dir)
+                b.is_dir
+                    .cmp(&a.is_dir)
                     .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
             });
         }
     }
-    node.children = children;
+}
 
-    node
+/// Merges chains of directories that contain nothing but a single
+/// subdirectory, like GitHub's `src/main/java` display.
+///
+/// The root itself is never merged, so the tree keeps its requested root.
+pub fn compact_tree(node: &mut TreeNode) {
+    for child in &mut node.children {
+        while child.has_single_dir_child() {
+            child.merge_single_child();
+        }
+        compact_tree(child);
+    }
+}
+
+/// Applies the depth limit, ignore rules, dirs-only and extension filters and
+/// sorting to a tree that was not built by `load_tree` (a path list or a
+/// loaded export). Only names and node types are consulted, never the disk;
+/// ignore rules see each entry's path relative to the root, so patterns such
+/// as `target/debug` apply.
+pub fn filter_tree(
+    node: &mut TreeNode,
+    max_depth: u32,
+    filter: &IgnoreFilter,
+    sort: SortMode,
+    opts: &TreeOptions,
+) {
+    filter_children(node, Path::new(""), max_depth, filter, sort, opts);
+}
+
+fn filter_children(
+    node: &mut TreeNode,
+    relative: &Path,
+    max_depth: u32,
+    filter: &IgnoreFilter,
+    sort: SortMode,
+    opts: &TreeOptions,
+) {
+    if max_depth == 0 {
+        node.children.clear();
+        return;
+    }
+    node.children.retain(|child| {
+        if filter.is_ignored_path(&relative.join(&child.name)) {
+            return false;
+        }
+        if child.is_dir {
+            return true;
+        }
+        if opts.dirs_only {
+            return false;
+        }
+        if opts.extensions.is_empty() {
+            return true;
+        }
+        let ext = child
+            .path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        opts.extensions.contains(&ext)
+            || child
+                .file_kind
+                .is_some_and(|k| opts.extensions.iter().any(|e| k.matches(e)))
+    });
+    for child in &mut node.children {
+        let relative = relative.join(&child.name);
+        filter_children(child, &relative, max_depth - 1, filter, sort, opts);
+    }
+    sort_children(&mut node.children, sort);
+}
+
+/// Picks a depth when none is given: expands more levels for small
+/// directories.
+pub fn default_depth(root: &Path) -> u32 {
+    let count = fs::read_dir(root)
+        .map(|entries| entries.filter_map(|e| e.ok()).count())
+        .unwrap_or(0);
+    if count <= 10 {
+        3
+    } else if count <= 30 {
+        2
+    } else {
+        1
+    }
 }
 
 /// Collects file metadata for a given path.
@@ -218,13 +461,11 @@ fn collect_metadata(path: &PathBuf) -> Option<NodeMetadata> {
 /// * `max_depth` - Optional cap on traversal depth (the 60-level safety limit).
 /// * `current_depth` - Current recursion depth (start with 0).
 /// * `filter` - Filter for ignoring files/directories.
-/// * `opts` - Additional traversal options.
 pub fn count_max_depth(
     root: &PathBuf,
     max_depth: u32,
     current_depth: u32,
     filter: &IgnoreFilter,
-    opts: &TreeOptions,
 ) -> u32 {
     if current_depth >= max_depth || !root.is_dir() {
         return current_depth;
@@ -260,7 +501,7 @@ pub fn count_max_depth(
 
     child_dirs
         .par_iter()
-        .map(|child| count_max_depth(child, max_depth, current_depth + 1, filter, opts))
+        .map(|child| count_max_depth(child, max_depth, current_depth + 1, filter))
         .max()
         .unwrap_or(current_depth)
 }
@@ -367,4 +608,56 @@ mod tests {
         assert!(!names.contains(&"excluded_dir"));
         assert!(names.contains(&"apple.txt"));
     }
+
+    #[test]
+    fn compact_merges_single_child_chains() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/main/java/app")).unwrap();
+        fs::write(dir.path().join("src/main/java/app/Main.java"), "").unwrap();
+        fs::write(dir.path().join("src/main/java/app/Util.java"), "").unwrap();
+        fs::create_dir_all(dir.path().join("docs")).unwrap();
+        fs::write(dir.path().join("docs/index.md"), "").unwrap();
+        let filter = IgnoreFilter::new(false, &[]);
+        let mut tree = load_tree(
+            &dir.path().to_path_buf(),
+            10,
+            0,
+            &filter,
+            SortMode::Kind,
+            &TreeOptions::default(),
+        );
+        compact_tree(&mut tree);
+
+        let names: Vec<&str> = tree.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["docs", "src/main/java/app"]);
+        let chain = &tree.children[1];
+        assert_eq!(chain.merged, vec!["src", "main", "java", "app"]);
+        assert_eq!(chain.path, dir.path().join("src/main/java/app"));
+        assert_eq!(chain.children.len(), 2);
+        // A directory holding only a file is not a chain
+        assert!(tree.children[0].merged.is_empty());
+    }
+
+    #[test]
+    fn extensions_match_detected_kind() {
+        let dir = setup_tree();
+        fs::write(dir.path().join("deploy"), "#!/usr/bin/env python3\n").unwrap();
+        let filter = IgnoreFilter::new(false, &[]);
+        let opts = TreeOptions {
+            extensions: vec!["py".to_string()],
+            detect_kinds: true,
+            ..TreeOptions::default()
+        };
+        let tree = load_tree(
+            &dir.path().to_path_buf(),
+            1,
+            0,
+            &filter,
+            SortMode::Name,
+            &opts,
+        );
+        let deploy = tree.children.iter().find(|c| c.name == "deploy").unwrap();
+        assert_eq!(deploy.file_kind.map(|k| k.name), Some("python"));
+        assert!(!tree.children.iter().any(|c| c.name == "apple.txt"));
+    }
 }
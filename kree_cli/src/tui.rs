@@ -18,10 +18,16 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph};
 
+use crate::filetype::{self, FileKind};
+use crate::heatmap::{ColorBy, Heatmap};
 use crate::ignore::IgnoreFilter;
-use crate::render::{ColorMap, IconMap, icon_for_node, is_executable};
+use crate::loc::{self, LineCounts, format_loc};
+use crate::notes::{Notes, annotate_tree};
+use crate::render::{IconMap, icon_for_node};
 use crate::search::levenshtein;
-use crate::tree::{SortMode, TreeNode, TreeOptions, load_tree};
+use crate::style::{StyleColor, Styler};
+use crate::theme::UiTheme;
+use crate::tree::{SortMode, TreeNode, TreeOptions, compact_tree, load_tree};
 
 /// Represents a flattened entry in the tree for list-based rendering.
 struct FlatEntry {
@@ -36,6 +42,12 @@ struct FlatEntry {
     continuation_depths: HashSet<usize>,
     is_symlink: bool,
     symlink_target: Option<PathBuf>,
+    file_kind: Option<FileKind>,
+    /// Heatmap color replacing the extension color, if `--color-by` is set.
+    heat: Option<StyleColor>,
+    note: Option<String>,
+    /// Line counts, if `--loc` is set.
+    loc: Option<LineCounts>,
 }
 
 #[derive(PartialEq)]
@@ -47,7 +59,9 @@ enum InputMode {
 /// Main application state for the TUI.
 struct App {
     tree: TreeNode,
-    root_path: PathBuf,
+    /// Root directory on disk, or `None` for trees that were not scanned
+    /// (`--fromfile`), which are never lazily loaded or reloaded.
+    root_path: Option<PathBuf>,
     /// Flattened list of entries currently visible (or available to be visible).
     entries: Vec<FlatEntry>,
     /// Set of expanded node IDs.
@@ -62,13 +76,18 @@ struct App {
     search_query: String,
     /// Set of entry indices that match the search query.
     search_matches: HashSet<usize>,
-    color_map: ColorMap,
+    styler: Styler,
     icon_map: IconMap,
+    /// Heatmap mode; the gradient is rebuilt whenever the tree changes.
+    color_by: Option<ColorBy>,
+    heatmap: Option<Heatmap>,
     should_quit: bool,
     filter: IgnoreFilter,
     sort: SortMode,
     max_depth: u32,
     opts: TreeOptions,
+    /// Notes for entries loaded lazily or on reload.
+    notes: Notes,
     /// Content of the file preview pane.
     preview_content: Vec<String>,
     /// Error message for file preview (if any).
@@ -79,17 +98,20 @@ impl App {
     #[allow(clippy::too_many_arguments)]
     fn new(
         tree: TreeNode,
-        root_path: PathBuf,
-        color_map: ColorMap,
+        root_path: Option<PathBuf>,
+        styler: Styler,
         icon_map: IconMap,
         filter: IgnoreFilter,
         sort: SortMode,
         max_depth: u32,
         opts: TreeOptions,
+        notes: Notes,
+        color_by: Option<ColorBy>,
     ) -> Self {
         let mut expanded = HashSet::new();
         expanded.insert(0); // root is always expanded
-        let entries = flatten_tree(&tree, &expanded);
+        let heatmap = color_by.map(|mode| Heatmap::new(mode, &tree));
+        let entries = flatten_tree(&tree, &expanded, heatmap.as_ref());
         let mut app = App {
             tree,
             root_path,
@@ -100,13 +122,16 @@ impl App {
             input_mode: InputMode::Normal,
             search_query: String::new(),
             search_matches: HashSet::new(),
-            color_map,
+            styler,
             icon_map,
+            color_by,
+            heatmap,
             should_quit: false,
             filter,
             sort,
             max_depth,
             opts,
+            notes,
             preview_content: Vec::new(),
             preview_error: None,
         };
@@ -115,7 +140,8 @@ impl App {
     }
 
     fn rebuild_entries(&mut self) {
-        self.entries = flatten_tree(&self.tree, &self.expanded);
+        self.heatmap = self.color_by.map(|mode| Heatmap::new(mode, &self.tree));
+        self.entries = flatten_tree(&self.tree, &self.expanded, self.heatmap.as_ref());
         if self.cursor >= self.entries.len() {
             self.cursor = self.entries.len().saturating_sub(1);
         }
@@ -154,7 +180,7 @@ impl App {
             let node_id = entry.node_id;
 
             // Lazy load: if this directory has no children loaded yet, load them now
-            if !entry.has_children {
+            if !entry.has_children && self.root_path.is_some() {
                 self.lazy_load_children(&path);
             }
 
@@ -165,12 +191,25 @@ impl App {
     }
 
     /// Loads children for a directory node on demand (lazy loading).
+    ///
+    /// In compact mode, a directory that turns out to hold a single
+    /// subdirectory is merged with it, so expanding a chain opens its
+    /// deepest directory.
     fn lazy_load_children(&mut self, path: &PathBuf) {
-        // Build a small subtree for just this directory (1 level deep)
-        let subtree = load_tree(path, 1, 0, &self.filter, self.sort, &self.opts);
+        let (filter, sort, opts, notes) = (&self.filter, self.sort, &self.opts, &self.notes);
+        // Build a small subtree for just one directory (1 level deep)
+        let load_children = |dir: &PathBuf| {
+            let mut subtree = load_tree(dir, 1, 0, filter, sort, opts);
+            annotate_tree(&mut subtree, notes);
+            subtree.children
+        };
         // Find the node in our tree and assign its children
         if let Some(node) = Self::find_node_mut(&mut self.tree, path) {
-            node.children = subtree.children;
+            node.children = load_children(path);
+            while opts.compact && node.has_single_dir_child() {
+                node.merge_single_child();
+                node.children = load_children(&node.path);
+            }
         }
     }
 
@@ -281,9 +320,7 @@ impl App {
             return;
         }
 
-        // Binary detection: check for null bytes in first 512 bytes
-        let check_len = buf.len().min(512);
-        if buf[..check_len].contains(&0) {
+        if filetype::is_binary(&buf) {
             self.preview_error = Some("Binary file — no preview available".to_string());
             return;
         }
@@ -366,14 +403,24 @@ impl App {
     }
 
     fn reload_tree(&mut self) {
+        let Some(ref root_path) = self.root_path else {
+            return;
+        };
         self.tree = load_tree(
-            &self.root_path,
+            root_path,
             self.max_depth,
             0,
             &self.filter,
             self.sort,
             &self.opts,
         );
+        annotate_tree(&mut self.tree, &self.notes);
+        if self.opts.compact {
+            compact_tree(&mut self.tree);
+        }
+        if self.opts.loc {
+            loc::sum_tree(&mut self.tree);
+        }
         self.expanded.clear();
         self.expanded.insert(0);
         self.search_query.clear();
@@ -432,12 +479,17 @@ impl App {
     }
 }
 
-fn flatten_tree(tree: &TreeNode, expanded: &HashSet<usize>) -> Vec<FlatEntry> {
+fn flatten_tree(
+    tree: &TreeNode,
+    expanded: &HashSet<usize>,
+    heatmap: Option<&Heatmap>,
+) -> Vec<FlatEntry> {
     let mut entries = Vec::new();
     let mut counter = 0usize;
     flatten_recursive(
         tree,
         expanded,
+        heatmap,
         0,
         true,
         &HashSet::new(),
@@ -447,9 +499,11 @@ fn flatten_tree(tree: &TreeNode, expanded: &HashSet<usize>) -> Vec<FlatEntry> {
     entries
 }
 
+#[allow(clippy::too_many_arguments)]
 fn flatten_recursive(
     node: &TreeNode,
     expanded: &HashSet<usize>,
+    heatmap: Option<&Heatmap>,
     depth: usize,
     is_last: bool,
     parent_continuations: &HashSet<usize>,
@@ -459,7 +513,7 @@ fn flatten_recursive(
     let node_id = *counter;
     *counter += 1;
 
-    let is_dir = node.path.is_dir();
+    let is_dir = node.is_dir;
     let has_children = !node.children.is_empty();
     let is_expanded = expanded.contains(&node_id);
 
@@ -480,6 +534,10 @@ fn flatten_recursive(
         continuation_depths: continuation_depths.clone(),
         is_symlink: node.is_symlink,
         symlink_target: node.symlink_target.clone(),
+        file_kind: node.file_kind,
+        heat: heatmap.and_then(|h| h.color_for(node, depth)),
+        note: node.note.clone(),
+        loc: node.loc,
     });
 
     if is_expanded && is_dir {
@@ -495,6 +553,7 @@ fn flatten_recursive(
             flatten_recursive(
                 child,
                 expanded,
+                heatmap,
                 depth + 1,
                 child_is_last,
                 &child_continuations,
@@ -578,30 +637,12 @@ fn auto_expand_recursive(
     has_match_below
 }
 
-fn rgb_to_color(r: u8, g: u8, b: u8) -> Color {
-    Color::Rgb(r, g, b)
-}
-
-fn style_for_entry(entry: &FlatEntry, color_map: &ColorMap) -> Style {
-    if entry.is_dir {
-        Style::default()
-            .fg(Color::Blue)
-            .add_modifier(Modifier::BOLD)
-    } else if is_executable(&entry.path) {
-        Style::default()
-            .fg(Color::Green)
-            .add_modifier(Modifier::BOLD)
-    } else {
-        let ext = entry
-            .path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("");
-        match color_map.get(&ext.to_lowercase()) {
-            Some(&(r, g, b)) => Style::default().fg(rgb_to_color(r, g, b)),
-            None => Style::default().fg(Color::White),
-        }
+fn style_for_entry(entry: &FlatEntry, styler: &Styler) -> Style {
+    let mut style = styler.style_for(&entry.path, entry.is_dir, entry.is_symlink, entry.file_kind);
+    if entry.heat.is_some() {
+        style.fg = entry.heat;
     }
+    style.to_tui()
 }
 
 fn tree_prefix(entry: &FlatEntry) -> String {
@@ -631,27 +672,51 @@ fn dir_indicator(entry: &FlatEntry) -> &'static str {
     }
 }
 
+/// Returns the style of the key badges in the status bar.
+fn key_style(ui: &UiTheme) -> Style {
+    Style::default()
+        .fg(ui.accent_fg.to_tui())
+        .bg(ui.accent.to_tui())
+}
+
+/// Returns a bordered block using the theme's border color.
+fn themed_block(ui: &UiTheme, borders: Borders) -> Block<'static> {
+    let block = Block::default().borders(borders);
+    match ui.border {
+        Some(color) => block.border_style(Style::default().fg(color.to_tui())),
+        None => block,
+    }
+}
+
 fn render_breadcrumb(app: &App) -> Paragraph<'static> {
-    let path_str = match app.entries.get(app.cursor) {
+    let ui = app.styler.ui();
+    let entry = app.entries.get(app.cursor);
+    let path_str = match entry {
         Some(entry) => entry.path.display().to_string(),
         None => String::new(),
     };
 
-    let line = Line::from(vec![
+    let mut spans = vec![
         Span::styled(
             "Kree",
             Style::default()
-                .fg(Color::Cyan)
+                .fg(ui.title.to_tui())
                 .add_modifier(Modifier::BOLD),
         ),
         Span::raw("   Path: "),
-        Span::styled(path_str, Style::default().fg(Color::Yellow)),
-    ]);
+        Span::styled(path_str, Style::default().fg(ui.highlight.to_tui())),
+    ];
+    if let Some(note) = entry.and_then(|e| e.note.as_ref()) {
+        spans.push(Span::styled(format!("  # {note}"), ui.metadata.to_tui()));
+    }
+    let line = Line::from(spans);
 
-    Paragraph::new(line).block(Block::default().borders(Borders::BOTTOM))
+    Paragraph::new(line).block(themed_block(ui, Borders::BOTTOM))
 }
 
 fn render_tree_panel(app: &App, area: Rect) -> Paragraph<'static> {
+    let ui = app.styler.ui();
+    let line_color = ui.tree_lines.map(|c| c.to_tui()).unwrap_or(Color::DarkGray);
     let viewport_height = area.height.saturating_sub(2) as usize; // borders
     let start = app.scroll_offset;
     let end = (start + viewport_height).min(app.entries.len());
@@ -662,30 +727,45 @@ fn render_tree_panel(app: &App, area: Rect) -> Paragraph<'static> {
         let entry = &app.entries[i];
         let prefix = tree_prefix(entry);
         let indicator = dir_indicator(entry).to_string();
-        let icon = icon_for_node(&entry.path, &app.icon_map);
+        let icon = icon_for_node(
+            &entry.path,
+            entry.is_dir,
+            entry.file_kind,
+            &app.icon_map,
+            entry.is_expanded,
+            app.styler.disk_lookups(),
+        );
         let icon_str = if icon.is_empty() {
             String::new()
         } else {
             format!("{icon} ")
         };
 
-        let name_style = style_for_entry(entry, &app.color_map);
+        let name_style = style_for_entry(entry, &app.styler);
         let is_cursor = i == app.cursor;
         let is_match = app.search_matches.contains(&i);
 
         let mut spans = vec![
-            Span::styled(prefix, Style::default().fg(Color::DarkGray)),
-            Span::styled(indicator, Style::default().fg(Color::Yellow)),
+            Span::styled(prefix, Style::default().fg(line_color)),
+            Span::styled(indicator, Style::default().fg(ui.highlight.to_tui())),
             Span::styled(icon_str, name_style),
         ];
 
         let mut final_style = name_style;
-        if is_cursor {
-            final_style = final_style.bg(Color::DarkGray).add_modifier(Modifier::BOLD);
-        }
         if is_match {
+            if let Some(color) = ui.search_match {
+                final_style = final_style.fg(color.to_tui());
+            }
             final_style = final_style.add_modifier(Modifier::UNDERLINED);
         }
+        if is_cursor {
+            final_style = final_style
+                .bg(ui.selection_bg.to_tui())
+                .add_modifier(Modifier::BOLD);
+            if let Some(color) = ui.selection_fg {
+                final_style = final_style.fg(color.to_tui());
+            }
+        }
 
         spans.push(Span::styled(entry.name.clone(), final_style));
 
@@ -697,17 +777,24 @@ fn render_tree_panel(app: &App, area: Rect) -> Paragraph<'static> {
                 .unwrap_or_else(|| "?".to_string());
             spans.push(Span::styled(
                 format!(" -> {target_str}"),
-                Style::default().fg(Color::Cyan),
+                Style::default().fg(ui.symlink.to_tui()),
+            ));
+        }
+        if let Some(counts) = &entry.loc {
+            spans.push(Span::styled(
+                format!("  {}", format_loc(counts)),
+                ui.metadata.to_tui(),
             ));
         }
 
         lines.push(Line::from(spans));
     }
 
-    Paragraph::new(lines).block(Block::default().title(" Tree ").borders(Borders::ALL))
+    Paragraph::new(lines).block(themed_block(ui, Borders::ALL).title(" Tree "))
 }
 
 fn render_preview_panel(app: &App) -> Paragraph<'static> {
+    let ui = app.styler.ui();
     let title = match app.entries.get(app.cursor) {
         Some(entry) => format!(" Preview: {} ", entry.name),
         None => " Preview ".to_string(),
@@ -716,7 +803,7 @@ fn render_preview_panel(app: &App) -> Paragraph<'static> {
     let lines: Vec<Line<'static>> = if let Some(err) = &app.preview_error {
         vec![Line::from(Span::styled(
             err.clone(),
-            Style::default().fg(Color::Red),
+            Style::default().fg(ui.error.to_tui()),
         ))]
     } else {
         app.preview_content
@@ -726,7 +813,7 @@ fn render_preview_panel(app: &App) -> Paragraph<'static> {
                 Line::from(vec![
                     Span::styled(
                         format!("{:>4} │ ", i + 1),
-                        Style::default().fg(Color::DarkGray),
+                        Style::default().fg(ui.line_numbers.to_tui()),
                     ),
                     Span::raw(line.clone()),
                 ])
@@ -734,45 +821,50 @@ fn render_preview_panel(app: &App) -> Paragraph<'static> {
             .collect()
     };
 
-    Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL))
+    Paragraph::new(lines).block(themed_block(ui, Borders::ALL).title(title))
 }
 
 fn render_status_bar(app: &App) -> Paragraph<'static> {
+    let ui = app.styler.ui();
+    let key = key_style(ui);
     let line = match app.input_mode {
         InputMode::Normal => Line::from(vec![
-            Span::styled(" q ", Style::default().fg(Color::Black).bg(Color::Cyan)),
+            Span::styled(" q ", key),
             Span::raw(" Quit  "),
-            Span::styled(" / ", Style::default().fg(Color::Black).bg(Color::Cyan)),
+            Span::styled(" / ", key),
             Span::raw(" Search  "),
-            Span::styled(" Enter ", Style::default().fg(Color::Black).bg(Color::Cyan)),
+            Span::styled(" Enter ", key),
             Span::raw(" Expand  "),
-            Span::styled(" h ", Style::default().fg(Color::Black).bg(Color::Cyan)),
+            Span::styled(" h ", key),
             Span::raw(" Collapse  "),
-            Span::styled(" j/k ", Style::default().fg(Color::Black).bg(Color::Cyan)),
+            Span::styled(" j/k ", key),
             Span::raw(" Navigate  "),
-            Span::styled(" r ", Style::default().fg(Color::Black).bg(Color::Cyan)),
+            Span::styled(" r ", key),
             Span::raw(" Reload"),
         ]),
         InputMode::Search => Line::from(vec![
             Span::styled(
                 " Search: ",
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(ui.highlight.to_tui())
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::styled(app.search_query.clone(), Style::default().fg(Color::White)),
-            Span::styled("█", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                app.search_query.clone(),
+                Style::default().fg(ui.file.to_tui()),
+            ),
+            Span::styled("█", Style::default().fg(ui.highlight.to_tui())),
             Span::raw("   "),
-            Span::styled(" Esc ", Style::default().fg(Color::Black).bg(Color::Cyan)),
+            Span::styled(" Esc ", key),
             Span::raw(" Cancel  "),
-            Span::styled(" Enter ", Style::default().fg(Color::Black).bg(Color::Cyan)),
+            Span::styled(" Enter ", key),
             Span::raw(" Confirm  "),
-            Span::styled(" ↑/↓ ", Style::default().fg(Color::Black).bg(Color::Cyan)),
+            Span::styled(" ↑/↓ ", key),
             Span::raw(" Jump match"),
         ]),
     };
 
-    Paragraph::new(line).block(Block::default().borders(Borders::TOP))
+    Paragraph::new(line).block(themed_block(ui, Borders::TOP))
 }
 
 fn ui(frame: &mut ratatui::Frame, app: &mut App) {
@@ -812,22 +904,28 @@ fn ui(frame: &mut ratatui::Frame, app: &mut App) {
 /// # Arguments
 ///
 /// * `tree` - The initial tree structure.
-/// * `root_path` - The root directory path.
-/// * `color_map` - Color configuration.
+/// * `root_path` - The root directory path, or `None` if the tree was not
+///   read from the disk.
+/// * `styler` - Color and text attribute configuration.
 /// * `icon_map` - Icon configuration.
 /// * `filter` - Ignore filter for reloading the tree.
 /// * `sort` - Sorting mode.
 /// * `max_depth` - Maximum depth for reloading the tree.
+/// * `opts` - Traversal options for reloading the tree.
+/// * `notes` - Notes shown for the selected entry.
+/// * `color_by` - Optional heatmap mode replacing extension colors.
 #[allow(clippy::too_many_arguments)]
 pub fn run(
     tree: TreeNode,
-    root_path: PathBuf,
-    color_map: ColorMap,
+    root_path: Option<PathBuf>,
+    styler: Styler,
     icon_map: IconMap,
     filter: IgnoreFilter,
     sort: SortMode,
     max_depth: u32,
     opts: TreeOptions,
+    notes: Notes,
+    color_by: Option<ColorBy>,
 ) -> io::Result<()> {
     // Setup terminal
     terminal::enable_raw_mode()?;
@@ -845,7 +943,7 @@ pub fn run(
     let mut terminal = Terminal::new(backend)?;
 
     let mut app = App::new(
-        tree, root_path, color_map, icon_map, filter, sort, max_depth, opts,
+        tree, root_path, styler, icon_map, filter, sort, max_depth, opts, notes, color_by,
     );
 
     // Main loop
@@ -36,6 +36,22 @@ fn create_dir(dir: &Path, name: &str) -> std::path::PathBuf {
     path
 }
 
+/// Helper function to create a named pipe.
+///
+/// # Arguments
+///
+/// * `dir` - The parent directory.
+/// * `name` - The name of the FIFO to create.
+#[cfg(unix)]
+fn create_fifo(dir: &Path, name: &str) {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(dir.join(name).as_os_str().as_bytes()).unwrap();
+    // SAFETY: `path` is a valid NUL-terminated string
+    let result = unsafe { libc::mkfifo(path.as_ptr(), 0o644) };
+    assert_eq!(result, 0, "Failed to create FIFO");
+}
+
 /// Verifies that Kree can render a basic directory tree.
 ///
 /// This test creates a temporary directory with a known structure:
@@ -138,3 +154,899 @@ fn test_hidden_files() {
         .stdout(predicate::str::contains("visible_file"))
         .stdout(predicate::str::contains(".hidden_file"));
 }
+
+/// Verifies that `--hyperlink always` wraps names in OSC 8 links and that
+/// piped output has no links by default.
+#[test]
+fn test_hyperlinks() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root = temp.path();
+
+    create_file(root, "linked.rs");
+
+    let mut cmd_always = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd_always
+        .arg(root.to_str().unwrap())
+        .arg("--hyperlink")
+        .arg("always")
+        .arg("--no-color");
+
+    cmd_always
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b]8;;file://"))
+        .stdout(predicate::str::contains(
+            "linked.rs\x1b\\linked.rs\x1b]8;;\x1b\\",
+        ));
+
+    // Piped output (not a TTY) gets no hyperlinks in auto mode
+    let mut cmd_auto = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd_auto.arg(root.to_str().unwrap()).arg("--no-color");
+
+    cmd_auto
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b]8;;").not());
+}
+
+/// Verifies that `--theme` changes the directory color.
+#[test]
+fn test_builtin_theme() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root = temp.path();
+
+    create_dir(root, "themed");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root.to_str().unwrap())
+        .arg("--theme")
+        .arg("gruvbox-dark")
+        .env("CLICOLOR_FORCE", "1")
+        .env("COLORTERM", "truecolor");
+
+    // gruvbox-dark directories are #83a598
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("38;2;131;165;152mthemed"));
+}
+
+/// Verifies that `--icons=SET` selects an icon set and that a bare `-i`
+/// does not swallow the path argument.
+#[test]
+fn test_icon_sets() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root = temp.path();
+
+    create_dir(root, "src");
+    create_file(root, "Cargo.toml");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root.to_str().unwrap())
+        .arg("--icons=emoji")
+        .arg("--no-color");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("🧩 src"))
+        .stdout(predicate::str::contains("🦀 Cargo.toml"));
+
+    let mut cmd_ascii = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd_ascii
+        .arg("--icons=ascii")
+        .arg(root.to_str().unwrap())
+        .arg("--no-color");
+
+    cmd_ascii
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+ src"))
+        .stdout(predicate::str::contains(". Cargo.toml"));
+
+    let mut cmd_default = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd_default
+        .arg("-i")
+        .arg(root.to_str().unwrap())
+        .arg("--no-color");
+
+    cmd_default
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{e7a8} Cargo.toml"));
+}
+
+/// Verifies that `--detect` lets `--extensions` match an extensionless script
+/// by its shebang.
+#[test]
+fn test_detect_file_kinds() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root = temp.path();
+
+    fs::write(root.join("deploy"), "#!/usr/bin/env python3\n").unwrap();
+    create_file(root, "notes.txt");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root.to_str().unwrap())
+        .args(["--detect", "-e", "py", "-F", "json"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"file_kind\": \"python\""))
+        .stdout(predicate::str::contains("notes.txt").not());
+}
+
+/// Verifies that `--detect` does not open named pipes, which would block
+/// until something writes to them.
+#[cfg(unix)]
+#[test]
+fn test_detect_skips_fifos() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root = temp.path();
+
+    create_fifo(root, "pipe");
+    fs::write(root.join("deploy"), "#!/bin/sh\n").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root.to_str().unwrap())
+        .args(["--detect", "-F", "json"])
+        .timeout(std::time::Duration::from_secs(10));
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"pipe\""))
+        .stdout(predicate::str::contains("\"file_kind\": \"shell\""));
+}
+
+/// Verifies that `--color-by size` paints the largest file with the hottest
+/// gradient color and does not print metadata columns.
+#[test]
+fn test_color_by_size() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root = temp.path();
+
+    fs::write(root.join("big.bin"), vec![0u8; 4096]).unwrap();
+    fs::write(root.join("small.txt"), "x").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root.to_str().unwrap())
+        .args(["--color-by", "size"])
+        .env("CLICOLOR_FORCE", "1")
+        .env("COLORTERM", "truecolor");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("38;2;255;90;50mbig.bin"))
+        .stdout(predicate::str::contains("4.0K").not());
+}
+
+/// Verifies that `--compact` merges single-child directory chains and counts
+/// every merged directory in the summary.
+#[test]
+fn test_compact_chains() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root = temp.path();
+
+    fs::create_dir_all(root.join("src/main/java")).unwrap();
+    create_file(&root.join("src/main/java"), "App.java");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root.to_str().unwrap())
+        .args(["--compact", "-d", "5", "--no-color"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("src/main/java"))
+        .stdout(predicate::str::contains("3 directories, 1 files"));
+}
+
+/// Verifies that notes from `.kreenotes` are rendered as aligned comments and
+/// that `--doc-notes` falls back to leading doc comments.
+#[test]
+fn test_kreenotes() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root = temp.path();
+
+    create_dir(root, "src");
+    fs::write(root.join("src/main.rs"), "//! CLI entry point.\n").unwrap();
+    fs::write(root.join(".kreenotes"), "src/  # application source\n").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root.to_str().unwrap())
+        .args(["--doc-notes", "--no-color"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "└── src           # application source",
+        ))
+        .stdout(predicate::str::contains("└── main.rs  # CLI entry point."));
+}
+
+/// Verifies that `--update-readme` regenerates marked blocks and that
+/// `--check` fails on stale blocks without touching the file.
+#[test]
+fn test_update_readme() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root = temp.path();
+
+    create_dir(root, "src");
+    create_file(&root.join("src"), "lib.rs");
+    let readme = root.join("README.md");
+    let stale = "# Project\n\n<!-- kree:start -d 2 -->\n```text\nold\n```\n<!-- kree:end -->\n";
+    fs::write(&readme, stale).unwrap();
+
+    let mut check = Command::new(env!("CARGO_BIN_EXE_kree"));
+    check.args(["--update-readme", readme.to_str().unwrap(), "--check"]);
+    check.assert().code(1).stderr(predicate::str::contains(
+        "README.md:3: tree block is out of date",
+    ));
+    assert_eq!(fs::read_to_string(&readme).unwrap(), stale);
+
+    let mut update = Command::new(env!("CARGO_BIN_EXE_kree"));
+    update.args(["--update-readme", readme.to_str().unwrap()]);
+    update.assert().success();
+    let contents = fs::read_to_string(&readme).unwrap();
+    assert!(contents.contains("```text\n└── .\n"));
+    assert!(contents.contains("└── lib.rs"));
+    assert!(!contents.contains('\x1b'));
+
+    let mut check = Command::new(env!("CARGO_BIN_EXE_kree"));
+    check.args(["--update-readme", readme.to_str().unwrap(), "--check"]);
+    check.assert().success();
+}
+
+/// Verifies that `--snapshot-check` passes on an unchanged layout and
+/// reports added, removed and type-changed entries with a non-zero exit code.
+#[test]
+fn test_snapshot_check() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root = temp.path();
+
+    create_dir(root, "src");
+    create_file(&root.join("src"), "main.rs");
+    create_file(root, "README.md");
+    let snapshot = root.join("layout.json");
+
+    let mut save = Command::new(env!("CARGO_BIN_EXE_kree"));
+    save.arg("--snapshot-save").arg(&snapshot).arg(root);
+    save.assert().success();
+
+    let mut check = Command::new(env!("CARGO_BIN_EXE_kree"));
+    check.arg("--snapshot-check").arg(&snapshot).arg(root);
+    check.assert().success();
+
+    create_dir(root, "build");
+    fs::remove_file(root.join("README.md")).unwrap();
+    fs::remove_file(root.join("src/main.rs")).unwrap();
+    create_dir(&root.join("src"), "main.rs");
+
+    let mut check = Command::new(env!("CARGO_BIN_EXE_kree"));
+    check.arg("--snapshot-check").arg(&snapshot).arg(root);
+    check
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("+ build/"))
+        .stdout(predicate::str::contains("- README.md"))
+        .stdout(predicate::str::contains("~ main.rs/ (was file)"))
+        .stdout(predicate::str::contains(
+            "1 added, 1 removed, 1 type changed",
+        ));
+
+    // `no_color` in ~/.kreerc applies to the drift report too
+    let home = TempDir::new().expect("Failed to create temp dir");
+    fs::write(home.path().join(".kreerc"), "[defaults]\nno_color = true\n").unwrap();
+    let mut check = Command::new(env!("CARGO_BIN_EXE_kree"));
+    check
+        .env("HOME", home.path())
+        .env("CLICOLOR_FORCE", "1")
+        .arg("--snapshot-check")
+        .arg(&snapshot)
+        .arg(root);
+    check
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("+ build/"))
+        .stdout(predicate::str::contains("\x1b[").not());
+
+    // A directory named like the old subcommand is just a path
+    create_dir(root, "snapshot");
+    create_file(&root.join("snapshot"), "golden.txt");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.current_dir(root).args(["snapshot", "--no-color"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("golden.txt"));
+}
+
+/// Verifies that `--snapshot-check` rescans with the options stored in the
+/// snapshot rather than those of its own command line.
+#[test]
+fn test_snapshot_check_uses_saved_options() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root = temp.path();
+
+    create_dir(root, "a");
+    create_dir(&root.join("a"), "b");
+    create_file(&root.join("a/b"), "deep.txt");
+    create_file(root, ".hidden");
+    let snapshots = TempDir::new().expect("Failed to create temp dir");
+    let shallow = snapshots.path().join("shallow.json");
+    let hidden = snapshots.path().join("hidden.json");
+
+    let mut save = Command::new(env!("CARGO_BIN_EXE_kree"));
+    save.arg("--snapshot-save")
+        .arg(&shallow)
+        .args(["-d", "1"])
+        .arg(root);
+    save.assert().success();
+    let mut save = Command::new(env!("CARGO_BIN_EXE_kree"));
+    save.arg("--snapshot-save").arg(&hidden).arg("-a").arg(root);
+    save.assert().success();
+
+    for snapshot in [&shallow, &hidden] {
+        let mut check = Command::new(env!("CARGO_BIN_EXE_kree"));
+        check.arg("--snapshot-check").arg(snapshot).arg(root);
+        check
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("matches"));
+    }
+}
+
+/// Verifies that `--diff` marks added, removed and changed entries, and that
+/// `--changed-only` hides unchanged ones.
+#[test]
+fn test_diff_directories() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let (a, b) = (create_dir(temp.path(), "a"), create_dir(temp.path(), "b"));
+
+    fs::write(a.join("same.txt"), "same").unwrap();
+    fs::write(b.join("same.txt"), "same").unwrap();
+    fs::write(a.join("app.bin"), "v1").unwrap();
+    fs::write(b.join("app.bin"), "v2 build").unwrap();
+    create_file(&a, "old.log");
+    create_dir(&b, "dist");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg("--diff")
+        .arg(&a)
+        .arg(&b)
+        .args(["--content", "--no-color"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("+ dist/"))
+        .stdout(predicate::str::contains("- old.log"))
+        .stdout(predicate::str::contains("~ app.bin (2B → 8B)"))
+        .stdout(predicate::str::contains("── same.txt"))
+        .stdout(predicate::str::contains("1 added, 1 removed, 1 modified"));
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg("--diff")
+        .arg(&a)
+        .arg(&b)
+        .args(["--content", "--changed-only", "-F", "json"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"status\": \"modified\""))
+        .stdout(predicate::str::contains("same.txt").not());
+}
+
+/// Verifies that `--fromfile -` builds the tree from a path list on stdin
+/// without the paths existing on disk.
+#[test]
+fn test_fromfile_stdin() {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.args(["--fromfile", "-", "--no-color", "-s", "kind"])
+        .write_stdin("missing/src/main.rs\nmissing/README.md\nmissing/docs/\n");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "     └── missing\n          ├── docs\n          ├── src\n",
+        ))
+        .stdout(predicate::str::contains("3 directories, 2 files"));
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.args(["--fromfile", "-", "-F", "json"])
+        .write_stdin("a/b.rs\0c.txt\0");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"type\": \"directory\""))
+        .stdout(predicate::str::contains("\"name\": \"b.rs\""));
+}
+
+/// Verifies that icons and doc notes of `--fromfile` entries do not come
+/// from unrelated files that exist under the same names.
+#[cfg(unix)]
+#[test]
+fn test_fromfile_skips_disk_lookups() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let cwd = temp.path();
+    fs::write(cwd.join("run"), "#!/bin/sh\n").unwrap();
+    fs::set_permissions(cwd.join("run"), fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(cwd.join("main.rs"), "//! Local docs.\n").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.current_dir(cwd)
+        .args([
+            "--fromfile",
+            "-",
+            "--no-color",
+            "--icons=ascii",
+            "--doc-notes",
+        ])
+        .write_stdin("run\nmain.rs\n");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(". run\n"))
+        .stdout(predicate::str::contains("Local docs").not());
+}
+
+#[test]
+fn test_load_export() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("app");
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(root.join("README.md"), "# app\n").unwrap();
+
+    let export = Command::new(env!("CARGO_BIN_EXE_kree"))
+        .args(["-F", "json", "-l"])
+        .arg(&root)
+        .output()
+        .unwrap();
+    assert!(export.status.success());
+    let snapshot = dir.path().join("app.json");
+    fs::write(&snapshot, &export.stdout).unwrap();
+    fs::remove_dir_all(&root).unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg("--load").arg(&snapshot).args(["-l", "--no-color"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("main.rs"))
+        .stdout(predicate::str::contains("13B"))
+        .stdout(predicate::str::contains("1 directories, 2 files"));
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg("--load").arg(dir.path().join("missing.json"));
+    cmd.assert().code(2);
+}
+
+#[test]
+fn test_export_schema() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+    let schema = Command::new(env!("CARGO_BIN_EXE_kree"))
+        .arg("--print-schema")
+        .output()
+        .unwrap();
+    assert!(schema.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&schema.stdout).unwrap();
+    assert_eq!(schema["properties"]["schema_version"]["const"], 1);
+
+    let export = Command::new(env!("CARGO_BIN_EXE_kree"))
+        .arg(dir.path())
+        .args(["-F", "json", "-d", "2", "-s", "name"])
+        .output()
+        .unwrap();
+    assert!(export.status.success());
+    let export: serde_json::Value = serde_json::from_slice(&export.stdout).unwrap();
+    assert_eq!(export["schema_version"], 1);
+    let root_path = fs::canonicalize(dir.path()).unwrap();
+    assert_eq!(export["root_path"], root_path.to_str().unwrap());
+    assert_eq!(export["options"]["depth"], 2);
+    assert_eq!(export["options"]["sort"], "name");
+    let file = &export["tree"]["children"][0];
+    assert_eq!(file["name"], "main.rs");
+    assert!(file["size"].is_null() && file["owner"].is_null());
+}
+
+/// Verifies that `--hash` and `--verify` skip named pipes instead of
+/// blocking on them.
+#[cfg(unix)]
+#[test]
+fn test_hash_skips_fifos() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    create_fifo(root, "pipe");
+    fs::write(root.join("data.txt"), "data\n").unwrap();
+
+    let export = Command::new(env!("CARGO_BIN_EXE_kree"))
+        .arg(root)
+        .args(["-F", "json", "--hash", "sha256"])
+        .timeout(std::time::Duration::from_secs(10))
+        .output()
+        .unwrap();
+    assert!(export.status.success());
+    assert!(String::from_utf8_lossy(&export.stdout).contains("\"pipe\""));
+
+    let manifests = TempDir::new().unwrap();
+    let manifest = manifests.path().join("manifest.json");
+    fs::write(&manifest, &export.stdout).unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg("--verify")
+        .arg(&manifest)
+        .arg(root)
+        .timeout(std::time::Duration::from_secs(10));
+    cmd.assert().success();
+}
+
+#[test]
+fn test_verify_manifest() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(root.join("README.md"), "# app\n").unwrap();
+    // Created before the export, like `kree -F json --hash sha256 > manifest.json`
+    let manifest = root.join("manifest.json");
+    fs::write(&manifest, "").unwrap();
+
+    let export = Command::new(env!("CARGO_BIN_EXE_kree"))
+        .arg(root)
+        .args(["-F", "json", "--hash", "sha256"])
+        .output()
+        .unwrap();
+    assert!(export.status.success());
+    let stdout = String::from_utf8(export.stdout.clone()).unwrap();
+    assert!(stdout.contains("\"hash\": \"sha256\""));
+    // sha256 of "# app\n"
+    assert!(stdout.contains(
+        "\"hash\": \"5f1590779fce327202d54b9ff4050658b8f890254614dba05c59db0bcc267dc3\""
+    ));
+    fs::write(&manifest, &export.stdout).unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg("--verify").arg(&manifest).arg(root);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("(2 files)"));
+
+    fs::write(root.join("src/main.rs"), "fn main() { panic!() }\n").unwrap();
+    fs::remove_file(root.join("README.md")).unwrap();
+    fs::write(root.join("extra.txt"), "").unwrap();
+    // `no_color` in ~/.kreerc applies to the report
+    let home = TempDir::new().unwrap();
+    fs::write(home.path().join(".kreerc"), "[defaults]\nno_color = true\n").unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.env("HOME", home.path())
+        .env("CLICOLOR_FORCE", "1")
+        .arg("--verify")
+        .arg(&manifest)
+        .arg(root);
+    cmd.assert()
+        .code(1)
+        .stdout(predicate::str::contains("\x1b[").not())
+        .stdout(predicate::str::contains("~ main.rs"))
+        .stdout(predicate::str::contains("- README.md"))
+        .stdout(predicate::str::contains("+ extra.txt"))
+        .stdout(predicate::str::contains("1 modified, 1 missing, 1 extra"));
+
+    // Manifests cover the whole tree, not just the default depth
+    let deep = root.join("a/b/c/d/e/f");
+    fs::create_dir_all(&deep).unwrap();
+    fs::write(deep.join("deep.txt"), "v1\n").unwrap();
+    let export = Command::new(env!("CARGO_BIN_EXE_kree"))
+        .arg(root)
+        .args(["-F", "json", "--hash", "blake3"])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&export.stdout).contains("deep.txt"));
+    fs::write(&manifest, &export.stdout).unwrap();
+    fs::write(deep.join("deep.txt"), "v2\n").unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg("--verify").arg(&manifest).arg(root);
+    cmd.assert()
+        .code(1)
+        .stdout(predicate::str::contains("~ deep.txt"));
+
+    let plain = Command::new(env!("CARGO_BIN_EXE_kree"))
+        .arg(root)
+        .args(["-F", "json"])
+        .output()
+        .unwrap();
+    fs::write(&manifest, &plain.stdout).unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg("--verify").arg(&manifest).arg(root);
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("has no content hashes"));
+}
+
+#[test]
+fn test_dupes() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join("backup")).unwrap();
+    fs::write(root.join("report.pdf"), "same contents").unwrap();
+    fs::write(root.join("backup/report.pdf"), "same contents").unwrap();
+    fs::write(root.join("unique.txt"), "only once").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root).args(["--dupes", "--no-color"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("└── report.pdf [dup #1]"))
+        .stdout(predicate::str::contains("unique.txt").not())
+        .stdout(predicate::str::contains(
+            "1 duplicate sets, 1 redundant copies, 13B reclaimable",
+        ));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_kree"))
+        .arg(root)
+        .args(["--dupes", "-F", "json"])
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["reclaimable_bytes"], 13);
+    assert_eq!(report["groups"][0]["paths"].as_array().unwrap().len(), 2);
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root).args(["--dupes", "-F", "html"]);
+    cmd.assert().code(2);
+}
+
+#[test]
+fn test_stats() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("src/bin")).unwrap();
+    fs::create_dir(root.join("empty")).unwrap();
+    fs::write(root.join("src/lib.rs"), "x".repeat(200)).unwrap();
+    fs::write(root.join("src/bin/cli.rs"), "x".repeat(100)).unwrap();
+    fs::write(root.join("notes.txt"), "hello").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root).args(["--stats", "--no-color", "--top", "1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "3 directories, 3 files, 0 symlinks\n305B total",
+        ))
+        .stdout(predicate::str::contains(
+            "Largest files\n    200B  src/lib.rs\n\n",
+        ))
+        .stdout(predicate::str::contains("Empty directories\n  empty/\n"))
+        .stdout(predicate::str::contains("└──").not());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_kree"))
+        .arg(root)
+        .args(["--stats", "-F", "json"])
+        .output()
+        .unwrap();
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stats["files"], 3);
+    assert_eq!(stats["extensions"][0]["name"], "rs");
+    assert_eq!(stats["largest_directories"][0]["path"], "src");
+    assert_eq!(stats["depths"].as_array().unwrap().len(), 3);
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root).args(["--stats", "-F", "csv"]);
+    cmd.assert().code(2);
+}
+
+#[test]
+fn test_loc() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(
+        root.join("src/main.rs"),
+        "// entry point\nfn main() {\n\n    run();\n}\n",
+    )
+    .unwrap();
+    fs::write(root.join("build.py"), "# build\nprint(1)\n").unwrap();
+    fs::write(root.join("logo.rs"), b"\x89PNG\0\0").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root).args(["--loc", "--no-color"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("├── src  3 loc\n"))
+        .stdout(predicate::str::contains("main.rs  3 loc\n"))
+        .stdout(predicate::str::contains("logo.rs\n"))
+        .stdout(predicate::str::contains(
+            "rust           1         3         1         1",
+        ))
+        .stdout(predicate::str::contains(
+            "Total          2         4         2         1",
+        ));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_kree"))
+        .arg(root)
+        .args(["--loc", "-F", "json"])
+        .output()
+        .unwrap();
+    let export: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(export["options"]["loc"], true);
+    assert_eq!(
+        export["tree"]["loc"],
+        serde_json::json!({"code": 4, "comment": 2, "blank": 1})
+    );
+
+    // Files below the smart default depth are counted too
+    fs::create_dir_all(root.join("src/a/b/c")).unwrap();
+    fs::write(root.join("src/a/b/c/deep.rs"), "fn deep() {}\n").unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root).args(["--loc", "--no-color"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("deep.rs  1 loc\n"))
+        .stdout(predicate::str::contains(
+            "Total          3         5         2         1",
+        ));
+
+    // With -d, the summary matches the totals of the cut-off directories
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root).args(["--loc", "--no-color", "-d", "1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("├── src  4 loc\n"))
+        .stdout(predicate::str::contains(
+            "rust           2         4         1         1",
+        ))
+        .stdout(predicate::str::contains(
+            "Total          3         5         2         1",
+        ));
+}
+
+/// Verifies that `--loc` skips a named pipe with a source extension instead
+/// of blocking on it.
+#[cfg(unix)]
+#[test]
+fn test_loc_skips_fifos() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join("src")).unwrap();
+    create_fifo(root, "pipe.rs");
+    create_fifo(&root.join("src"), "pipe.rs");
+    fs::write(root.join("src/lib.rs"), "fn f() {}\n").unwrap();
+
+    // The second FIFO is below the depth limit
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(root)
+        .args(["--loc", "--no-color", "-d", "1"])
+        .timeout(std::time::Duration::from_secs(10));
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("├── src  1 loc\n"));
+}
+
+#[test]
+fn test_html_export() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path()).args(["-F", "html", "-l"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("<!DOCTYPE html>"))
+        .stdout(predicate::str::contains("data-name=\"main.rs\""))
+        .stdout(predicate::str::contains("class=\"size\""))
+        .stdout(predicate::str::contains("1 directories, 1 files"));
+}
+
+#[test]
+fn test_diagram_exports() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("src/deep")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "").unwrap();
+    fs::write(dir.path().join("src/deep/hidden.rs"), "").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path()).args(["-F", "dot", "-d", "2"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("digraph tree {"))
+        .stdout(predicate::str::contains("label=\"main.rs\""))
+        .stdout(predicate::str::contains("hidden.rs").not());
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path()).args(["-F", "mermaid"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("graph TD\n"))
+        .stdout(predicate::str::contains("--> n2[\"deep\"]"));
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path()).args(["-F", "plantuml"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("@startwbs\n"))
+        .stdout(predicate::str::ends_with("@endwbs\n"));
+}
+
+#[test]
+fn test_tabular_exports() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path()).args(["-F", "xml"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("<directory name=\"src\">"))
+        .stdout(predicate::str::contains("<file name=\"main.rs\"></file>"))
+        .stdout(predicate::str::contains("<directories>1</directories>"));
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path()).args(["-F", "csv", "-l"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "path,type,depth,size,modified,mode,owner,hash,code,comment,blank\n",
+        ))
+        .stdout(predicate::str::contains("main.rs,file,2,13,"));
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path()).args(["-F", "ndjson"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "{\"id\":2,\"parent\":1,\"depth\":2,\"name\":\"main.rs\"",
+    ));
+}
+
+#[test]
+fn test_document_exports() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("my_docs")).unwrap();
+    fs::write(dir.path().join("my_docs/guide.adoc"), "").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path()).args(["-F", "latex"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("\\dirtree{%\n.1 \\textbf{"))
+        .stdout(predicate::str::contains(
+            ".2 \\textbf{my\\_docs}/.\n.3 guide.adoc.\n}\n",
+        ));
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path()).args(["-F", "asciidoc"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "** *pass:c[my_docs]*/\n*** guide.adoc\n",
+    ));
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path()).args(["-F", "org"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "  - =my_docs/=\n    - guide.adoc\n",
+    ));
+}
+
+#[test]
+fn test_markdown_styles() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path()).args(["-F", "markdown", "--md-links"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "  - **[src](src)**/\n    - [main.rs](src/main.rs)\n",
+    ));
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path())
+        .args(["-F", "markdown", "--md-style", "tree"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("```text\n└── "))
+        .stdout(predicate::str::contains(
+            "     └── src\n          └── main.rs\n",
+        ))
+        .stdout(predicate::str::contains("\x1b[").not());
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kree"));
+    cmd.arg(dir.path())
+        .args(["-F", "markdown", "--md-style", "table"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("| src/main.rs | file |\n"));
+}